@@ -0,0 +1,90 @@
+//! GPS position from the `Exif.GPSInfo` tags.
+
+use chrono::{NaiveDateTime, NaiveTime};
+
+use crate::{Metadata, Value};
+
+/// GPS position of an image, see [`Metadata::gps`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gps {
+    /// Latitude in decimal degrees, negative south of the equator.
+    pub latitude: f64,
+    /// Longitude in decimal degrees, negative west of Greenwich.
+    pub longitude: f64,
+    /// Altitude in meters, negative below sea level.
+    pub altitude: Option<f64>,
+    /// Time of the GPS fix in UTC.
+    pub timestamp: Option<NaiveDateTime>,
+}
+
+/// Combine up to three components, e.g. degrees, minutes and seconds, into a
+/// single number.
+fn sexagesimal(value: &Value) -> Option<f64> {
+    match value {
+        Value::List(components) if (1..=3).contains(&components.len()) => components
+            .iter()
+            .zip([1., 60., 3600.])
+            .try_fold(0., |sum, (component, divisor)| {
+                Some(sum + component.as_f64()? / divisor)
+            }),
+        value => value.as_f64(),
+    }
+}
+
+impl Metadata {
+    /// Read a coordinate from `tag`, negated if `reference` is `negative`.
+    fn coordinate(&self, tag: &str, reference: &str, negative: &str) -> Option<f64> {
+        let coordinate = sexagesimal(&self.get_tag_value(tag).ok()?)?;
+        match self.get_tag_value(reference) {
+            Ok(Value::String(reference)) if reference.trim() == negative => Some(-coordinate),
+            _ => Some(coordinate),
+        }
+    }
+
+    /// The GPS position of the image, combining the degrees, minutes and seconds
+    /// of `Exif.GPSInfo.GPSLatitude` and `GPSLongitude` with their reference
+    /// into signed decimal degrees. Also reads `GPSAltitude` with its reference,
+    /// and `GPSDateStamp` with `GPSTimeStamp`. Returns `None` if the image has no
+    /// latitude or longitude.
+    pub fn gps(&self) -> Option<Gps> {
+        let latitude = self.coordinate(
+            "Exif.GPSInfo.GPSLatitude",
+            "Exif.GPSInfo.GPSLatitudeRef",
+            "S",
+        )?;
+        let longitude = self.coordinate(
+            "Exif.GPSInfo.GPSLongitude",
+            "Exif.GPSInfo.GPSLongitudeRef",
+            "W",
+        )?;
+        let altitude = self
+            .get_tag_value("Exif.GPSInfo.GPSAltitude")
+            .ok()
+            .and_then(|altitude| altitude.as_f64())
+            .map(
+                |altitude| match self.get_tag_value("Exif.GPSInfo.GPSAltitudeRef") {
+                    Ok(Value::Integer(1)) => -altitude,
+                    _ => altitude,
+                },
+            );
+        let timestamp = match (
+            self.get_tag_value("Exif.GPSInfo.GPSDateStamp"),
+            self.get_tag_value("Exif.GPSInfo.GPSTimeStamp"),
+        ) {
+            (Ok(Value::Date(date)), Ok(time)) => sexagesimal(&time)
+                .filter(|hours| (0. ..24.).contains(hours))
+                .and_then(|hours| {
+                    let seconds = (hours * 3600.).round() as u32;
+                    NaiveTime::from_num_seconds_from_midnight_opt(seconds, 0)
+                })
+                .map(|time| date.and_time(time)),
+            _ => None,
+        };
+        Some(Gps {
+            latitude,
+            longitude,
+            altitude,
+            timestamp,
+        })
+    }
+}
@@ -1,16 +1,22 @@
 //! Minimal safe wrapper over libexiv2.
 //!
 //! A thin C++ shim (see `shim.{h,cc}`) bound through the `cxx` crate. Only the
-//! read-only operations rahmen actually uses are exposed: loading an image and
-//! reading a single tag as an interpreted string. The unavoidable FFI `unsafe`
-//! is confined to this crate so the rahmen crate can keep `forbid(unsafe_code)`.
+//! read-only operations rahmen actually uses are exposed: loading an image,
+//! reading a single tag as an interpreted string or a typed [`Value`], the GPS
+//! position, and listing all tags. The unavoidable FFI `unsafe` is confined to
+//! this crate so the rahmen crate can keep `forbid(unsafe_code)`.
 
 use std::error::Error;
 use std::ffi::OsStr;
 use std::fmt;
 
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 use cxx::UniquePtr;
 
+mod gps;
+
+pub use gps::Gps;
+
 // SAFETY: this is the cxx FFI bridge. The `unsafe extern "C++"` block only
 // declares the signatures of the C++ functions in `shim.cc`; cxx generates the
 // marshalling glue and verifies the C++ side matches at compile time. The
@@ -18,6 +24,44 @@ use cxx::UniquePtr;
 // return owned values, and all C++ exceptions are caught and surfaced as Err.
 #[cxx::bridge(namespace = "rahmen_exiv2")]
 mod ffi {
+    /// One metadatum as read by the shim, see [`super::Datum`].
+    struct RawDatum {
+        key: String,
+        type_name: String,
+        count: usize,
+        raw: String,
+        interpreted: String,
+    }
+
+    /// How to read the components of a [`RawValue`].
+    enum ValueKind {
+        /// `integers`, one per component.
+        Integer,
+        /// `integers`, numerator and denominator per component.
+        Rational,
+        /// `floats`, one per component.
+        Float,
+        /// `strings`, a single string.
+        Text,
+        /// `bytes`.
+        Binary,
+        /// `strings`, a single date formatted as `YYYY-MM-DD`.
+        Date,
+        /// `strings`, a single time formatted as `HH:MM:SS+HH:MM`.
+        Time,
+        /// `strings`, one per item of an Xmp array.
+        List,
+    }
+
+    /// A value as read by the shim, see [`super::Value`].
+    struct RawValue {
+        kind: ValueKind,
+        integers: Vec<i64>,
+        floats: Vec<f64>,
+        strings: Vec<String>,
+        bytes: Vec<u8>,
+    }
+
     unsafe extern "C++" {
         include!("src/shim.h");
 
@@ -29,6 +73,12 @@ mod ffi {
 
         /// Interpreted (human-readable) string for `key`.
         fn tag_interpreted(image: &Image, key: &str) -> Result<String>;
+
+        /// Typed value for `key`.
+        fn tag_value(image: &Image, key: &str) -> Result<RawValue>;
+
+        /// All Exif, then Iptc, then Xmp metadata of the image.
+        fn all_data(image: &Image) -> Result<Vec<RawDatum>>;
     }
 }
 
@@ -44,6 +94,139 @@ impl fmt::Display for Exiv2Error {
 
 impl Error for Exiv2Error {}
 
+/// A typed metadata value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// An integer, e.g. `Exif.Photo.ISOSpeedRatings`.
+    Integer(i64),
+    /// A rational number as numerator and denominator, e.g. `Exif.Photo.FNumber`.
+    Rational(i64, i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    String(String),
+    /// Undefined data, e.g. `Exif.Photo.MakerNote`.
+    Binary(Vec<u8>),
+    /// A date, e.g. `Iptc.Application2.DateCreated`.
+    Date(NaiveDate),
+    /// A time with an optional UTC offset, e.g. `Iptc.Application2.TimeCreated`.
+    Time(NaiveTime, Option<FixedOffset>),
+    /// A date and time with an optional UTC offset, e.g. `Exif.Photo.DateTimeOriginal`
+    /// or `Xmp.xmp.CreateDate`.
+    DateTime(NaiveDateTime, Option<FixedOffset>),
+    /// Several components, e.g. the three rationals of `Exif.GPSInfo.GPSLatitude`, or
+    /// the items of an Xmp array.
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// The value as a number, for integers, rationals and floats. `None` for
+    /// other values and rationals with a zero denominator.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(value) => Some(*value as f64),
+            Self::Rational(_, 0) => None,
+            Self::Rational(numerator, denominator) => Some(*numerator as f64 / *denominator as f64),
+            Self::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Parse a string value, recognizing the date and time formats of Exif
+    /// (`2026:06:11 14:24:00`) and Xmp (`2026-06-11T14:24:00+02:00`).
+    fn from_text(text: String) -> Self {
+        if let Ok(date_time) = chrono::DateTime::parse_from_rfc3339(&text) {
+            return Self::DateTime(date_time.naive_local(), Some(*date_time.offset()));
+        }
+        for format in ["%Y:%m:%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+            if let Ok(date_time) = NaiveDateTime::parse_from_str(&text, format) {
+                return Self::DateTime(date_time, None);
+            }
+        }
+        for format in ["%Y:%m:%d", "%Y-%m-%d"] {
+            if let Ok(date) = NaiveDate::parse_from_str(&text, format) {
+                return Self::Date(date);
+            }
+        }
+        Self::String(text)
+    }
+
+    /// Parse a time formatted as `HH:MM:SS+HH:MM`.
+    fn from_time(text: String) -> Self {
+        let (time, offset) = text.split_at_checked(8).unwrap_or((&text, ""));
+        match NaiveTime::parse_from_str(time, "%H:%M:%S") {
+            Ok(time) => Self::Time(time, offset.parse().ok()),
+            Err(_) => Self::String(text),
+        }
+    }
+
+    /// A single component as it is, several as a list.
+    fn from_components(mut components: Vec<Value>) -> Self {
+        if components.len() == 1 {
+            components.remove(0)
+        } else {
+            Self::List(components)
+        }
+    }
+}
+
+impl From<ffi::RawValue> for Value {
+    fn from(raw: ffi::RawValue) -> Self {
+        let text = || raw.strings.first().cloned().unwrap_or_default();
+        match raw.kind {
+            ffi::ValueKind::Integer => {
+                Self::from_components(raw.integers.into_iter().map(Self::Integer).collect())
+            }
+            ffi::ValueKind::Rational => Self::from_components(
+                raw.integers
+                    .chunks_exact(2)
+                    .map(|pair| Self::Rational(pair[0], pair[1]))
+                    .collect(),
+            ),
+            ffi::ValueKind::Float => {
+                Self::from_components(raw.floats.into_iter().map(Self::Float).collect())
+            }
+            ffi::ValueKind::Binary => Self::Binary(raw.bytes),
+            ffi::ValueKind::Date => match NaiveDate::parse_from_str(&text(), "%Y-%m-%d") {
+                Ok(date) => Self::Date(date),
+                Err(_) => Self::String(text()),
+            },
+            ffi::ValueKind::Time => Self::from_time(text()),
+            ffi::ValueKind::List => {
+                Self::List(raw.strings.into_iter().map(Self::from_text).collect())
+            }
+            _ => Self::from_text(text()),
+        }
+    }
+}
+
+/// A single metadatum of an image, e.g. `Exif.Photo.FNumber`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Datum {
+    /// Key to read the datum with, e.g. `Exif.Photo.FNumber`.
+    pub key: String,
+    /// Name of the exiv2 type, e.g. `Rational` or `XmpText`.
+    pub type_name: String,
+    /// Number of components of the value.
+    pub count: usize,
+    /// Value as stored, e.g. `28/10`.
+    pub raw: String,
+    /// Interpreted (human-readable) value, e.g. `F2.8`.
+    pub interpreted: String,
+}
+
+impl From<ffi::RawDatum> for Datum {
+    fn from(datum: ffi::RawDatum) -> Self {
+        Self {
+            key: datum.key,
+            type_name: datum.type_name,
+            count: datum.count,
+            raw: datum.raw,
+            interpreted: datum.interpreted,
+        }
+    }
+}
+
 /// Image metadata loaded from a file.
 pub struct Metadata {
     image: UniquePtr<ffi::Image>,
@@ -66,4 +249,24 @@ impl Metadata {
     pub fn get_tag_interpreted_string(&self, tag: &str) -> Result<String, Exiv2Error> {
         ffi::tag_interpreted(&self.image, tag).map_err(|e| Exiv2Error(e.what().to_string()))
     }
+
+    /// Read `tag` as a typed value. Returns an error when the tag is absent or
+    /// the key is invalid.
+    pub fn get_tag_value(&self, tag: &str) -> Result<Value, Exiv2Error> {
+        ffi::tag_value(&self.image, tag)
+            .map(Value::from)
+            .map_err(|e| Exiv2Error(e.what().to_string()))
+    }
+
+    /// All Exif, Iptc and Xmp data of the image, in this order and in the
+    /// order stored within each family.
+    pub fn data(&self) -> Result<Vec<Datum>, Exiv2Error> {
+        let data = ffi::all_data(&self.image).map_err(|e| Exiv2Error(e.what().to_string()))?;
+        Ok(data.into_iter().map(Datum::from).collect())
+    }
+
+    /// Iterate over all Exif, Iptc and Xmp data of the image, see [`Self::data`].
+    pub fn iter(&self) -> Result<impl Iterator<Item = Datum>, Exiv2Error> {
+        Ok(self.data()?.into_iter())
+    }
 }
@@ -1,4 +1,5 @@
 #include "src/shim.h"
+#include "rahmen-exiv2/src/lib.rs.h"
 
 #include <stdexcept>
 #include <string>
@@ -47,4 +48,154 @@ rust::String tag_interpreted(const Image &image, rust::Str key) {
   throw std::runtime_error("unknown tag family: " + k);
 }
 
+namespace {
+
+// Value::toLong was replaced by toInt64 in exiv2 0.28.
+#ifdef EXIV2_TEST_VERSION
+#if EXIV2_TEST_VERSION(0, 28, 0)
+#define RAHMEN_EXIV2_INT64
+#endif
+#endif
+
+int64_t to_int64(const Exiv2::Value &value, size_t n) {
+#ifdef RAHMEN_EXIV2_INT64
+  return value.toInt64(n);
+#else
+  return value.toLong(static_cast<long>(n));
+#endif
+}
+
+const Exiv2::Value &find_value(const Image &image, const std::string &k) {
+  if (k.rfind("Exif.", 0) == 0) {
+    auto &data = image.img->exifData();
+    auto pos = data.findKey(Exiv2::ExifKey(k));
+    if (pos != data.end()) {
+      return pos->value();
+    }
+  } else if (k.rfind("Iptc.", 0) == 0) {
+    auto &data = image.img->iptcData();
+    auto pos = data.findKey(Exiv2::IptcKey(k));
+    if (pos != data.end()) {
+      return pos->value();
+    }
+  } else if (k.rfind("Xmp.", 0) == 0) {
+    auto &data = image.img->xmpData();
+    auto pos = data.findKey(Exiv2::XmpKey(k));
+    if (pos != data.end()) {
+      return pos->value();
+    }
+  } else {
+    throw std::runtime_error("unknown tag family: " + k);
+  }
+  throw std::runtime_error("tag not found: " + k);
+}
+
+}  // namespace
+
+RawValue tag_value(const Image &image, rust::Str key) {
+  const auto &value = find_value(image, std::string(key));
+  const size_t count = static_cast<size_t>(value.count());
+  RawValue raw{};
+  switch (value.typeId()) {
+    case Exiv2::unsignedByte:
+    case Exiv2::unsignedShort:
+    case Exiv2::unsignedLong:
+    case Exiv2::unsignedLongLong:
+    case Exiv2::signedByte:
+    case Exiv2::signedShort:
+    case Exiv2::signedLong:
+    case Exiv2::signedLongLong:
+    case Exiv2::tiffIfd:
+    case Exiv2::tiffIfd8:
+      raw.kind = ValueKind::Integer;
+      for (size_t i = 0; i < count; ++i) {
+        raw.integers.push_back(to_int64(value, i));
+      }
+      break;
+    case Exiv2::unsignedRational:
+    case Exiv2::signedRational:
+      raw.kind = ValueKind::Rational;
+      for (size_t i = 0; i < count; ++i) {
+        auto rational = value.toRational(i);
+        raw.integers.push_back(rational.first);
+        raw.integers.push_back(rational.second);
+      }
+      break;
+    case Exiv2::tiffFloat:
+    case Exiv2::tiffDouble:
+      raw.kind = ValueKind::Float;
+      for (size_t i = 0; i < count; ++i) {
+        raw.floats.push_back(value.toFloat(i));
+      }
+      break;
+    case Exiv2::undefined: {
+      raw.kind = ValueKind::Binary;
+      std::vector<unsigned char> bytes(static_cast<size_t>(value.size()));
+      value.copy(bytes.data(), Exiv2::littleEndian);
+      for (auto byte : bytes) {
+        raw.bytes.push_back(byte);
+      }
+      break;
+    }
+    case Exiv2::date:
+      raw.kind = ValueKind::Date;
+      raw.strings.push_back(rust::String::lossy(value.toString()));
+      break;
+    case Exiv2::time:
+      raw.kind = ValueKind::Time;
+      raw.strings.push_back(rust::String::lossy(value.toString()));
+      break;
+    case Exiv2::xmpBag:
+    case Exiv2::xmpSeq:
+    case Exiv2::xmpAlt:
+      raw.kind = ValueKind::List;
+      for (size_t i = 0; i < count; ++i) {
+        raw.strings.push_back(rust::String::lossy(value.toString(i)));
+      }
+      break;
+    case Exiv2::langAlt:
+      // The text in the default language.
+      raw.kind = ValueKind::Text;
+      raw.strings.push_back(rust::String::lossy(value.toString(0)));
+      break;
+    default:
+      raw.kind = ValueKind::Text;
+      raw.strings.push_back(rust::String::lossy(value.toString()));
+      break;
+  }
+  return raw;
+}
+
+namespace {
+
+// Append every datum in `data` to `out`. `exif` is the surrounding ExifData to
+// interpret Exif tags fully, and null for the other families.
+template <class Data>
+void collect(const Data &data, const Exiv2::ExifData *exif,
+             rust::Vec<RawDatum> &out) {
+  for (const auto &datum : data) {
+    // typeName() is null for types exiv2 doesn't know.
+    const char *type_name = datum.typeName();
+    out.push_back(RawDatum{
+        rust::String::lossy(datum.key()),
+        rust::String::lossy(type_name ? type_name : "Unknown"),
+        // count() is long in exiv2 0.27 and size_t in 0.28.
+        static_cast<size_t>(datum.count()),
+        rust::String::lossy(datum.toString()),
+        rust::String::lossy(datum.print(exif)),
+    });
+  }
+}
+
+}  // namespace
+
+rust::Vec<RawDatum> all_data(const Image &image) {
+  rust::Vec<RawDatum> out;
+  const auto &exif = image.img->exifData();
+  collect(exif, &exif, out);
+  collect(image.img->iptcData(), nullptr, out);
+  collect(image.img->xmpData(), nullptr, out);
+  return out;
+}
+
 }  // namespace rahmen_exiv2
@@ -1,8 +1,9 @@
 #pragma once
 
 // C++ shim exposing a minimal, read-only slice of libexiv2 to Rust via cxx.
-// Only what rahmen needs: open an image and read a tag as an interpreted
-// (human-readable) string across the Exif, Iptc and Xmp families.
+// Only what rahmen needs: open an image, read a tag as an interpreted
+// (human-readable) string across the Exif, Iptc and Xmp families, and list all
+// tags.
 
 #include <memory>
 
@@ -12,6 +13,10 @@
 
 namespace rahmen_exiv2 {
 
+// Shared with Rust; defined in the cxx-generated header included by shim.cc.
+struct RawDatum;
+struct RawValue;
+
 // Opaque-to-Rust handle owning a loaded exiv2 image with its metadata read.
 // Defined fully here so cxx can instantiate the std::unique_ptr<Image> glue.
 //
@@ -33,4 +38,11 @@ std::unique_ptr<Image> open_image(rust::Str path);
 // the key is malformed, or the family prefix is unknown.
 rust::String tag_interpreted(const Image &image, rust::Str key);
 
+// Return the typed value for `key`. Throws like tag_interpreted.
+RawValue tag_value(const Image &image, rust::Str key);
+
+// Return every Exif, then Iptc, then Xmp datum with its key, type name, count,
+// raw and interpreted value.
+rust::Vec<RawDatum> all_data(const Image &image);
+
 }  // namespace rahmen_exiv2
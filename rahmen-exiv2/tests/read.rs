@@ -1,8 +1,11 @@
 //! Integration tests reading the committed `testdata/tagged.jpg` fixture, which
 //! carries one Exif, one Iptc and one Xmp tag (see the crate history for how it
-//! was produced).
+//! was produced), and `testdata/gps.jpg`, which carries the `Exif.GPSInfo` tags
+//! of a position in Santiago de Chile, 33°26'52.8"S 70°40'12"W at 570 m.
 
-use rahmen_exiv2::Metadata;
+use chrono::NaiveDate;
+use rahmen_exiv2::Gps;
+use rahmen_exiv2::{Metadata, Value};
 
 fn fixture() -> Metadata {
     Metadata::new_from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/tagged.jpg"))
@@ -47,3 +50,81 @@ fn unknown_family_is_error() {
 fn missing_file_is_error() {
     assert!(Metadata::new_from_path("/nonexistent/path/to/image.jpg").is_err());
 }
+
+#[test]
+fn lists_all_data() {
+    let data = fixture().data().unwrap();
+    let position = |key: &str| data.iter().position(|d| d.key == key).unwrap();
+    let (exif, iptc, xmp) = (
+        position("Exif.Photo.DateTimeOriginal"),
+        position("Iptc.Application2.City"),
+        position("Xmp.dc.creator"),
+    );
+    assert!(exif < iptc && iptc < xmp);
+
+    let date = &data[exif];
+    assert_eq!(date.type_name, "Ascii");
+    assert_eq!(date.raw, "2026:06:11 14:24:00");
+    assert_eq!(date.interpreted, "2026:06:11 14:24:00");
+    assert_eq!(data[iptc].interpreted, "Zurich");
+    assert_eq!(data[xmp].interpreted, "Moritz Hoffmann");
+    assert!(date.count > 0);
+}
+
+#[test]
+fn reads_typed_values() {
+    let m = fixture();
+    let date_time = NaiveDate::from_ymd_opt(2026, 6, 11)
+        .unwrap()
+        .and_hms_opt(14, 24, 0)
+        .unwrap();
+    assert_eq!(
+        m.get_tag_value("Exif.Photo.DateTimeOriginal").unwrap(),
+        Value::DateTime(date_time, None)
+    );
+    assert_eq!(
+        m.get_tag_value("Iptc.Application2.City").unwrap(),
+        Value::String("Zurich".into())
+    );
+    assert_eq!(
+        m.get_tag_value("Xmp.dc.creator").unwrap(),
+        Value::List(vec![Value::String("Moritz Hoffmann".into())])
+    );
+    assert!(m.get_tag_value("Iptc.Application2.CountryName").is_err());
+}
+
+#[test]
+fn numeric_values() {
+    assert_eq!(Value::Rational(28, 10).as_f64(), Some(2.8));
+    assert_eq!(Value::Rational(1, 0).as_f64(), None);
+    assert_eq!(Value::Integer(200).as_f64(), Some(200.));
+    assert_eq!(Value::String("200".into()).as_f64(), None);
+}
+
+#[test]
+fn reads_gps() {
+    let gps = Metadata::new_from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/gps.jpg"))
+        .expect("fixture should load")
+        .gps()
+        .unwrap();
+    let Gps {
+        latitude,
+        longitude,
+        altitude,
+        timestamp,
+    } = gps;
+    assert!((latitude + 33.448).abs() < 1e-9, "{latitude}");
+    assert!((longitude + 70.67).abs() < 1e-9, "{longitude}");
+    assert_eq!(altitude, Some(570.));
+    assert_eq!(
+        timestamp,
+        NaiveDate::from_ymd_opt(2026, 6, 11)
+            .unwrap()
+            .and_hms_opt(14, 24, 30)
+    );
+}
+
+#[test]
+fn no_gps() {
+    assert_eq!(fixture().gps(), None);
+}
@@ -1,16 +1,18 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::mpsc::Receiver;
 use std::time::{Duration, Instant};
 
-use clap::{Arg, Command, value_parser};
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
 use font_kit::loaders::freetype::Font;
 use image::{DynamicImage, GenericImageView};
 use log::{error, info, warn};
-use pyo3::prelude::*;
-use pyo3::types::PyList;
+use timely::communication::allocator::Thread;
 use timely::dataflow::channels::pact::Pipeline;
 use timely::dataflow::operators::capture::Event;
 use timely::dataflow::operators::{
@@ -19,20 +21,46 @@ use timely::dataflow::operators::{
 };
 use timely::dataflow::{InputHandle, ProbeHandle, Scope};
 use timely::order::Product;
-use timely::worker::Config;
+use timely::worker::{Config, Worker};
 
 use pathfinder_geometry::rect::RectI;
 use rahmen::Vector;
-use rahmen::config::Settings;
-use rahmen::dataflow::{Configuration, FormatText, ResizeImage};
+use rahmen::check::{check_settings, check_status_line, read_settings};
+use rahmen::config::{
+    Action, Filter as ScaleFilter, Focus, GeoNames, Output, Rotation, Settings, Sharpen,
+};
+#[cfg(unix)]
+use rahmen::control::ControlSocket;
+use rahmen::control::{Command as ControlCommand, Reply, Status};
+use rahmen::dataflow::{
+    Configuration, DimImage, FormatText, InfoText, MatImage, PanZoomImage, ResizeImage,
+};
 use rahmen::display::Display;
+#[cfg(feature = "drm")]
+use rahmen::display_drm::DrmDisplay;
+use rahmen::display_file::FileDisplay;
 use rahmen::display_framebuffer::FramebufferDisplay;
 #[cfg(feature = "minifb")]
 use rahmen::display_minifb::MinifbDisplay;
+use rahmen::display_tee::{Mirrors, TeeDisplay};
+#[cfg(feature = "terminal")]
+use rahmen::display_terminal::{TerminalDisplay, TerminalGraphics};
 use rahmen::errors::{RahmenError, RahmenResult};
 use rahmen::font::FontRenderer;
-use rahmen::provider::{Provider, StatusLineFormatter, load_image_from_path};
+use rahmen::geocode::Geocoder;
+use rahmen::input::KeyMap;
+use rahmen::ken_burns::focal_point;
+use rahmen::mat::Passepartout;
+use rahmen::power::SysfsPower;
+use rahmen::provider::{
+    History, Provider, StatusLineFormatter, add_python_paths, load_image_from_path,
+    reload_python_module,
+};
 use rahmen::provider_list::ListProvider;
+use rahmen::schedule::{DimSchedule, Dimming, PowerSchedule};
+#[cfg(feature = "http")]
+use rahmen::stream::{FrameStream, StreamDisplay};
+use rahmen::tags;
 
 static SPLASH: &[u8] = include_bytes!("rahmen.png");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -79,9 +107,803 @@ enum Render {
 
 type RunResult<T> = Result<T, RunControl>;
 
+/// Settings which only take effect when Rahmen starts
+const RESTART_SETTINGS: &[&str] = &[
+    "ken_burns",
+    "margins",
+    "mat",
+    "border",
+    "rotate",
+    "dither",
+    "blank_path",
+    "display_time",
+    "time_format",
+    "output",
+    "http",
+    "input",
+    "control_socket",
+    "mqtt",
+    "watch_config",
+];
+
 #[cfg(unix)]
 const SYSTEM_CONFIG_PATH: &str = "/etc/rahmen.toml";
 
+/// Command line options shared by all slideshows
+#[derive(Debug, Clone)]
+struct Options {
+    buffer_max_size: usize,
+    font: String,
+    time: Option<f64>,
+    font_size: Option<f32>,
+}
+
+/// A slideshow: the dataflow preparing the frames, and the state of the display loop feeding it
+struct Slideshow {
+    /// Configuration file to reload the settings from
+    config_path: Option<PathBuf>,
+    /// Settings at the start, to tell which changes need a restart
+    initial_settings: toml::Table,
+    /// Place names loaded for the `geonames` settings, reused while they don't change
+    geocoder: Option<(GeoNames, Arc<Geocoder>)>,
+    options: Options,
+    worker: Worker<Thread>,
+    input_configuration: InputHandle<Duration, Configuration>,
+    probe: ProbeHandle<Duration>,
+    output: Receiver<Event<Duration, RunResult<Render>>>,
+    start_time: Instant,
+    /// Wall clock time at `start_time`, to read the schedules at a point in dataflow time
+    start_local: chrono::DateTime<chrono::Local>,
+    delay: Duration,
+    next_image_at: Duration,
+    dimensions: Option<(u32, u32)>,
+    dim_schedule: DimSchedule,
+    current_dimming: Option<Dimming>,
+    power_schedule: PowerSchedule,
+    /// Whether the dataflow renders the time, for `display_time` and for dimming to only the clock
+    show_clock: bool,
+    /// Font size of the status line
+    font_size: f32,
+    /// Resampling filter and unsharp mask to scale images with
+    scaling: (ScaleFilter, Option<Sharpen>),
+    powered: bool,
+    /// Time left to show the current image while the slides stop advancing
+    paused: Option<Duration>,
+    /// Whether the screen is switched off on request
+    blanked: bool,
+    /// Brightness set on request, overriding the dimming schedule
+    brightness: Option<f32>,
+    /// Whether to terminate on the next step
+    quit: bool,
+    show_status_line: bool,
+    show_info: bool,
+    current: Rc<RefCell<Current>>,
+    /// Formatter of the status line, shared with the dataflow
+    status_line_formatter: Rc<RefCell<StatusLineFormatter>>,
+}
+
+/// The image currently shown by a slideshow
+#[derive(Debug, Default)]
+struct Current {
+    path: Option<PathBuf>,
+    /// Index in the history
+    index: usize,
+    caption: Option<String>,
+}
+
+impl Slideshow {
+    /// Build the dataflow for a slideshow of the images from `provider`
+    fn new(
+        settings: Settings,
+        config_path: Option<PathBuf>,
+        provider: Box<dyn Provider<PathBuf>>,
+        options: &Options,
+    ) -> RahmenResult<Self> {
+        let initial_settings = toml::Table::try_from(&settings).unwrap_or_default();
+        let mut geocoder = None;
+        let status_line_formatter =
+            Rc::new(RefCell::new(build_formatter(&settings, &mut geocoder)?));
+        let formatter = Rc::clone(&status_line_formatter);
+
+        let buffer_max_size = options.buffer_max_size;
+        let font = Font::from_path(&options.font, 0).unwrap();
+        let font_renderer = FontRenderer::with_font(font);
+
+        let duration_millis = (options.time.or(settings.delay).unwrap_or(90.) * 1000f64) as u64;
+        let delay = Duration::from_millis(duration_millis);
+        info!("Delay: {:?}", delay);
+
+        // font size to use (px)
+        let font_size_f = options.font_size.or(settings.font_size).unwrap_or(30.);
+
+        let show_time = settings.display_time.unwrap_or(false);
+        let dim_schedule = DimSchedule::new(settings.dim.unwrap_or_default());
+        let show_clock = show_time || dim_schedule.has_clock_only();
+        let scaling = (
+            settings.filter.unwrap_or(ScaleFilter::Triangle),
+            settings.sharpen,
+        );
+        let power_schedule = PowerSchedule::new(settings.power_off.unwrap_or_default());
+        let time_format = settings.time_format.unwrap_or("%H:%M:%S".into());
+        let ken_burns = settings.ken_burns;
+        let margins = settings.margins.unwrap_or_default();
+        let margin_offset = Vector::new(
+            margins.left.unwrap_or(0) as _,
+            margins.top.unwrap_or(0) as _,
+        );
+        let passepartout = if settings.mat.is_some() || settings.border.is_some() {
+            Some(Passepartout::new(settings.mat, settings.border)?)
+        } else {
+            None
+        };
+        let inset = passepartout.as_ref().map_or(0, Passepartout::inset);
+
+        // remember the images shown to be able to go back
+        let mut history = History::new(provider);
+        let current = Rc::new(RefCell::new(Current::default()));
+        let current_image = Rc::clone(&current);
+        let current_caption = Rc::clone(&current);
+
+        // initialization for timely dataflow
+        let allocator = timely::communication::allocator::Thread::new();
+        let mut worker = timely::worker::Worker::new(Config::default(), allocator);
+
+        // input: #1 timeline #2 screen resolution
+        let mut input_configuration: InputHandle<_, Configuration> = InputHandle::new();
+        // to gather information about progress
+        let mut probe = ProbeHandle::new();
+
+        let output = worker.dataflow(|scope| {
+            let configuration_stream = input_configuration.to_stream(scope);
+
+            let img_path_stream = scope.scoped::<Product<_, u32>, _, _>("File loading", |inner| {
+                let (handle, cycle) = inner.loop_variable(1);
+                let (ok, err) = configuration_stream
+                    .filter(|c| {
+                        matches!(
+                            c,
+                            Configuration::Tick | Configuration::Previous | Configuration::Goto(_)
+                        )
+                    })
+                    .enter(inner)
+                    .concat(&cycle)
+                    // obtain next path, or go back in the history
+                    .flat_map(move |c| {
+                        let path = match c {
+                            Configuration::Previous => history.previous_image().map(Ok),
+                            Configuration::Goto(path) => Some(Ok(history.goto(path))),
+                            _ => Some(fatal_err(history.next_image())),
+                        };
+                        if let Some(Ok(path)) = &path {
+                            let mut current = current_image.borrow_mut();
+                            current.path = Some(path.clone());
+                            current.index = history.index();
+                        }
+                        path
+                    })
+                    // Load image
+                    .and_then(move |ref path| {
+                        suppress_err(
+                            load_image_from_path(path, Some(buffer_max_size))
+                                .map(|img| (path.clone(), Arc::new(img))),
+                        )
+                    })
+                    .branch(|_t, d| d.as_ref().err() == Some(&RunControl::Suppressed));
+                err.map(|_| Configuration::Tick).connect_loop(handle);
+                ok.leave()
+            });
+            let err_stream = img_path_stream.err();
+
+            let mut buffer = vec![];
+            let mut configuration_buffer = vec![];
+            let mut stash: HashMap<Duration, String> = HashMap::new();
+            let mut clock_only_stash: HashMap<Duration, bool> = HashMap::new();
+            let mut current_text = None;
+            let mut clock_only = false;
+            // time of the pending clock update, if any
+            let mut next_update = None;
+
+            let mut status_line_stream = img_path_stream
+                .ok()
+                .flat_map(move |(p, img)| {
+                    let status = formatter
+                        .borrow()
+                        .format(&p)
+                        .map_err(|err| warn!("Failed to format the status line: {}", err))
+                        .ok();
+                    current_caption.borrow_mut().caption = status.clone();
+                    let status = status?;
+                    let info = format!(
+                        "{} ({}x{})",
+                        p.display(),
+                        img.dimensions().0,
+                        img.dimensions().1
+                    );
+                    Some((status, info))
+                })
+                .concat(&configuration_stream.flat_map(|c| match c {
+                    Configuration::Greeting(text) => Some((text.clone(), text)),
+                    _ => None,
+                }))
+                .info_text(&configuration_stream)
+                .inspect(|loc| info!("Status line: {}", loc));
+            if show_clock {
+                status_line_stream = status_line_stream.binary_notify(
+                    &configuration_stream,
+                    Pipeline,
+                    Pipeline,
+                    "Show time",
+                    Some(Duration::from_secs(0)),
+                    move |input1, input2, output, not: &mut Notificator<Duration>| {
+                        input1.for_each(|cap, data| {
+                            data.swap(&mut buffer);
+                            if let Some(text) = buffer.drain(..).next_back() {
+                                *stash.entry(*cap.time()).or_default() = text;
+                                not.notify_at(cap.retain());
+                            }
+                        });
+                        input2.for_each(|cap, data| {
+                            data.swap(&mut configuration_buffer);
+                            let clock_only = configuration_buffer
+                                .drain(..)
+                                .filter_map(|configuration| match configuration {
+                                    Configuration::Dim(dimming) => {
+                                        Some(dimming.is_some_and(|d| d.clock_only))
+                                    }
+                                    _ => None,
+                                })
+                                .next_back();
+                            if let Some(clock_only) = clock_only {
+                                clock_only_stash.insert(*cap.time(), clock_only);
+                                not.notify_at(cap.retain());
+                            }
+                        });
+                        not.for_each(|cap, _cnt, not| {
+                            if next_update == Some(*cap.time()) {
+                                next_update = None;
+                            }
+                            let mut changed = false;
+                            if let Some(text) = stash.remove(cap.time()) {
+                                current_text = Some(text);
+                                changed = true;
+                            }
+                            if let Some(flag) = clock_only_stash.remove(cap.time()) {
+                                changed |= flag != clock_only;
+                                clock_only = flag;
+                            }
+                            let now = chrono::Local::now();
+                            let delay =
+                                std::cmp::max(50, 1000 - now.timestamp_subsec_millis() as u64);
+                            if next_update.is_none() && !not.frontier(0).is_empty() {
+                                let mut next_time = *cap.time() + Duration::from_millis(delay);
+                                while !not.frontier(0).less_equal(&next_time) {
+                                    next_time += Duration::from_secs(1);
+                                }
+                                not.notify_at(cap.delayed(&next_time));
+                                next_update = Some(next_time);
+                            }
+                            if clock_only {
+                                output
+                                    .session(&cap)
+                                    .give(now.format(&time_format).to_string());
+                            } else if let Some(text) = &current_text {
+                                if show_time {
+                                    let time_text =
+                                        format!("[{}] {}", now.format(&time_format), text);
+                                    output.session(&cap).give(time_text);
+                                } else if changed {
+                                    output.session(&cap).give(text.clone());
+                                }
+                            }
+                        });
+                    },
+                );
+            }
+            let status_line_stream =
+                status_line_stream.map(|s| s.split('\n').map(Into::into).collect());
+
+            // reserve the outer margins, all content is shifted by the margin offset at the end
+            let margin_configuration_stream = configuration_stream.map(move |c| match c {
+                Configuration::ScreenDimensions(width, height) => Configuration::ScreenDimensions(
+                    width.saturating_sub(margins.horizontal()),
+                    height.saturating_sub(margins.vertical()),
+                ),
+                configuration => configuration,
+            });
+
+            let text_img_stream = status_line_stream
+                .format_text(&margin_configuration_stream, font_renderer, 2)
+                .map(move |(key, anchor, img)| (key, anchor + margin_offset, img));
+
+            let adjusted_configuration_stream = {
+                let mut stash: HashMap<_, Vec<_>> = HashMap::new();
+                let mut buffer = vec![];
+                // Hack: adjust screen size for the resize operator to reserve space for the status line
+                let mut current_font_size = None;
+                let mut current_font_canvas_vstretch = None;
+
+                margin_configuration_stream.unary_notify(
+                    Pipeline,
+                    "Adjust configuration",
+                    None,
+                    move |input, output, not| {
+                        input.for_each(|cap, data| {
+                            data.swap(&mut buffer);
+                            stash.entry(*cap.time()).or_default().append(&mut buffer);
+                            not.notify_at(cap.retain());
+                        });
+                        not.for_each(|cap, _, _not| {
+                            if let Some(updates) = stash.remove(cap.time()) {
+                                output.session(&cap).give_iterator(updates.into_iter().map(
+                                    |configuration| {
+                                        match configuration {
+                                            Configuration::FontSize(font_size) => {
+                                                current_font_size = Some(font_size);
+                                                Configuration::FontSize(font_size)
+                                            }
+                                            Configuration::FontCanvasVStretch(
+                                                font_canvas_vstretch,
+                                            ) => {
+                                                current_font_canvas_vstretch =
+                                                    Some(font_canvas_vstretch);
+                                                Configuration::FontCanvasVStretch(
+                                                    font_canvas_vstretch,
+                                                )
+                                            }
+                                            Configuration::ScreenDimensions(width, height) => {
+                                                Configuration::ScreenDimensions(
+                                                    width,
+                                                    height.saturating_sub(
+                                                        (current_font_size.unwrap_or(0.)
+                                                            * current_font_canvas_vstretch
+                                                                .unwrap_or(1.0))
+                                                        .ceil()
+                                                            as u32,
+                                                    ),
+                                                )
+                                            }
+                                            configuration => configuration,
+                                        }
+                                    },
+                                ));
+                            }
+                        });
+                    },
+                )
+            };
+
+            let splash_stream = configuration_stream.flat_map(|c| match c {
+                Configuration::Splash(img) => Some(img),
+                _ => None,
+            });
+            // leave room for the mat and border around the image
+            let inset_configuration_stream = adjusted_configuration_stream.map(move |c| match c {
+                Configuration::ScreenDimensions(width, height) => Configuration::ScreenDimensions(
+                    width.saturating_sub(2 * inset),
+                    height.saturating_sub(2 * inset),
+                ),
+                configuration => configuration,
+            });
+            let mut img_stream = if let Some(ken_burns) = ken_burns {
+                let focus = ken_burns.focus.unwrap_or(Focus::Random);
+                img_path_stream
+                    .ok()
+                    .map(move |(path, img)| (img, focal_point(focus, path)))
+                    .concat(&splash_stream.map(|img| (img, None)))
+                    .pan_zoom_image(&inset_configuration_stream, 1, ken_burns)
+            } else {
+                img_path_stream
+                    .ok()
+                    .map(|(_, img)| img)
+                    .concat(&splash_stream)
+                    .resize_image(&inset_configuration_stream, 1)
+            };
+            if let Some(passepartout) = passepartout {
+                img_stream = img_stream.mat_image(&adjusted_configuration_stream, passepartout, 0);
+            }
+            let img_stream =
+                img_stream.map(move |(key, anchor, img)| (key, anchor + margin_offset, img));
+
+            let mut size_stash: HashMap<usize, _> = HashMap::new();
+            let mut input_buffer: HashMap<_, Vec<(_, _, _)>> = HashMap::new();
+
+            let composed_img_stream =
+                img_stream
+                    .concat(&text_img_stream)
+                    .dim_image(&configuration_stream, 2)
+                    .unary_notify(
+                        Pipeline,
+                        "Infer blanking",
+                        None,
+                        move |input, output, not| {
+                            let mut buffer = vec![];
+                            input.for_each(|time, data| {
+                                data.swap(&mut buffer);
+                                input_buffer
+                                    .entry(*time.time())
+                                    .or_default()
+                                    .append(&mut buffer);
+                                not.notify_at(time.retain());
+                            });
+                            not.for_each(|time, _count, _not| {
+                                if let Some(updates) = input_buffer.remove(time.time()) {
+                                    output.session(&time).give_iterator(
+                                        updates.into_iter().flat_map(|(key, anchor, img)| {
+                                            let rect = RectI::new(
+                                                anchor,
+                                                Vector::new(
+                                                    img.dimensions().0 as _,
+                                                    img.dimensions().1 as _,
+                                                ),
+                                            );
+                                            size_stash
+                                                .insert(key, rect)
+                                                .into_iter()
+                                                .flat_map(move |old_rect| {
+                                                    compute_blanking(key, rect, old_rect)
+                                                })
+                                                .chain(Some(Render::Image(key, anchor, img)))
+                                        }),
+                                    );
+                                }
+                            })
+                        },
+                    );
+
+            err_stream
+                .map(Err)
+                .concat(&composed_img_stream.map(Ok))
+                .probe_with(&mut probe)
+                .capture()
+        });
+
+        input_configuration.send(Configuration::FontSize(font_size_f));
+        input_configuration.send(Configuration::Delay(delay));
+        input_configuration.send(Configuration::Filter(scaling.0));
+        input_configuration.send(Configuration::Sharpen(scaling.1));
+        // enlarge font canvas vertically by this factor (default given here: 1.4)
+        input_configuration.send(Configuration::FontCanvasVStretch(1.4));
+        match image::load_from_memory(SPLASH) {
+            Ok(image) => {
+                info!("Sending splash screen");
+                input_configuration.send(Configuration::Splash(Arc::new(image)));
+            }
+            Err(err) => warn!("Failed to load splash screen: {}", err),
+        }
+        input_configuration.send(Configuration::Greeting(format!("Rahmen {}", VERSION)));
+
+        let start_time = Instant::now();
+        let start_local = chrono::Local::now();
+        Ok(Self {
+            config_path,
+            initial_settings,
+            geocoder,
+            options: options.clone(),
+            worker,
+            input_configuration,
+            probe,
+            output,
+            start_time,
+            start_local,
+            delay,
+            next_image_at: start_time.elapsed() + Duration::from_secs(1),
+            dimensions: None,
+            dim_schedule,
+            current_dimming: None,
+            power_schedule,
+            show_clock,
+            font_size: font_size_f,
+            scaling,
+            powered: true,
+            paused: None,
+            blanked: false,
+            brightness: None,
+            quit: false,
+            status_line_formatter,
+            show_status_line: true,
+            show_info: false,
+            current,
+        })
+    }
+
+    /// Advance the slideshow and show its updates on `display`. Returns an error to terminate.
+    fn step(&mut self, display: &mut dyn Display) -> RahmenResult<()> {
+        if self.quit {
+            info!("Terminating");
+            return Err(RahmenError::Terminate);
+        }
+        let now = self.start_time.elapsed();
+        let time_of_day =
+            (self.start_local + chrono::TimeDelta::from_std(now).unwrap_or_default()).time();
+
+        let power = !self.power_schedule.is_off_at(time_of_day) && !self.blanked;
+        if power != self.powered {
+            self.powered = power;
+            let result = if self.powered {
+                info!("Powering on");
+                // show the current image for a full period
+                self.reset_period();
+                display.power_on()
+            } else {
+                info!("Powering off");
+                display.power_off()
+            };
+            if let Err(err) = result {
+                warn!("Failed to switch power: {}", err);
+            }
+        }
+
+        // slides only advance while the screen is on
+        if self.powered && self.paused.is_none() && self.next_image_at < now {
+            self.input_configuration.send(Configuration::Tick);
+            self.next_image_at = now + self.delay;
+        }
+
+        let dimming = match self.brightness {
+            Some(brightness) => (brightness < 1.).then_some(Dimming {
+                brightness,
+                warm: false,
+                clock_only: false,
+            }),
+            None => self.dim_schedule.dimming_at(time_of_day),
+        };
+        if dimming != self.current_dimming {
+            info!("Dimming: {:?}", dimming);
+            self.current_dimming = dimming;
+            self.input_configuration.send(Configuration::Dim(dimming));
+        }
+
+        if Some(display.dimensions()) != self.dimensions {
+            self.dimensions = Some(display.dimensions());
+            self.input_configuration
+                .send(Configuration::ScreenDimensions(
+                    display.dimensions().0,
+                    display.dimensions().1,
+                ));
+        }
+        // nothing is shown while the screen is off, so Ken Burns frames and the clock wait until
+        // it is on again; the updates sent meanwhile are processed then
+        if !self.powered {
+            return Ok(());
+        }
+        self.input_configuration.advance_to(now);
+        while self.probe.less_than(&now) {
+            self.worker.step();
+        }
+        let mut has_update = false;
+        let result = match self.output.try_iter().all(|result| match result {
+            // Continue processing on progress messages
+            Event::Progress(_) => true,
+            // Handle data messages by rending an image and determining whether to terminate
+            Event::Messages(_, r) => {
+                let mut terminate = false;
+                for result in r {
+                    match result {
+                        Ok(Render::Image(key, anchor, ref img)) => {
+                            has_update = true;
+                            if let Err(err) = display.render(key, anchor, img.as_ref()) {
+                                error!("Render failed: {}", err);
+                                terminate = true;
+                            }
+                        }
+                        Ok(Render::Blank(key, anchor, size)) => {
+                            has_update = true;
+                            if let Err(err) = display.blank(key, anchor, size) {
+                                error!("Blank failed: {}", err);
+                                terminate = true;
+                            }
+                        }
+                        Err(RunControl::Terminate) => terminate = true,
+                        _ => {}
+                    }
+                }
+                !terminate
+            }
+        }) {
+            true => Ok(()),
+            false => Err(RahmenError::Terminate),
+        };
+        if result.is_ok() && has_update {
+            display.update()
+        } else {
+            result
+        }
+    }
+
+    /// Perform an action requested by the user
+    fn handle(&mut self, action: Action) {
+        info!("Action: {:?}", action);
+        match action {
+            Action::Next => {
+                self.input_configuration.send(Configuration::Tick);
+                self.reset_period();
+            }
+            Action::Previous => {
+                self.input_configuration.send(Configuration::Previous);
+                self.reset_period();
+            }
+            Action::Pause => self.set_paused(self.paused.is_none()),
+            Action::StatusLine => {
+                self.show_status_line = !self.show_status_line;
+                self.input_configuration
+                    .send(Configuration::StatusLine(self.show_status_line));
+            }
+            Action::Info => {
+                self.show_info = !self.show_info;
+                self.input_configuration
+                    .send(Configuration::ShowInfo(self.show_info));
+            }
+            Action::Blank => self.blanked = !self.blanked,
+        }
+    }
+
+    /// Stop or continue advancing the slides. The time left for the current image is kept while
+    /// paused.
+    fn set_paused(&mut self, paused: bool) {
+        let now = self.start_time.elapsed();
+        match (paused, self.paused) {
+            (true, None) => self.paused = Some(self.next_image_at.saturating_sub(now)),
+            (false, Some(remaining)) => {
+                self.paused = None;
+                self.next_image_at = now + remaining;
+            }
+            _ => {}
+        }
+    }
+
+    /// Show the current image for a full period, counted from resuming if paused
+    fn reset_period(&mut self) {
+        self.next_image_at = self.start_time.elapsed() + self.delay;
+        if self.paused.is_some() {
+            self.paused = Some(self.delay);
+        }
+    }
+
+    /// Perform a command from a control interface
+    fn command(&mut self, command: ControlCommand) -> Reply {
+        // status queries are polled, e.g. by the MQTT client
+        if command != ControlCommand::Status {
+            info!("Command: {:?}", command);
+        }
+        match command {
+            ControlCommand::Next => self.handle(Action::Next),
+            ControlCommand::Previous => self.handle(Action::Previous),
+            ControlCommand::Pause => self.set_paused(true),
+            ControlCommand::Resume => self.set_paused(false),
+            ControlCommand::Goto { path } => {
+                if !Path::new(&path).is_file() {
+                    return Reply::error(format!("Not a file: {}", path));
+                }
+                self.input_configuration
+                    .send(Configuration::Goto(path.into()));
+                self.reset_period();
+            }
+            ControlCommand::SetDelay { seconds } => {
+                if !seconds.is_finite() || seconds <= 0. {
+                    return Reply::error("The delay must be positive");
+                }
+                self.set_delay(Duration::from_secs_f64(seconds));
+            }
+            ControlCommand::SetFontSize { size } => {
+                if !size.is_finite() || size <= 0. {
+                    return Reply::error("The font size must be positive");
+                }
+                self.set_font_size(size);
+            }
+            ControlCommand::ShowMessage { text } => {
+                self.input_configuration.send(Configuration::Greeting(text))
+            }
+            ControlCommand::ReloadConfig => {
+                if let Err(err) = self.reload() {
+                    // keep the previous settings
+                    error!("Failed to reload the configuration: {}", err);
+                    return Reply::error(format!("Failed to reload the configuration: {}", err));
+                }
+            }
+            ControlCommand::Status => {
+                let current = self.current.borrow();
+                let remaining = self.paused.unwrap_or_else(|| {
+                    self.next_image_at.saturating_sub(self.start_time.elapsed())
+                });
+                return Reply::status(Status {
+                    path: current.path.as_ref().map(|path| path.display().to_string()),
+                    index: current.index,
+                    caption: current.caption.clone(),
+                    remaining: remaining.as_secs_f64(),
+                    paused: self.paused.is_some(),
+                    powered: self.powered,
+                    brightness: self
+                        .current_dimming
+                        .map_or(1., |dimming| dimming.brightness),
+                });
+            }
+            ControlCommand::SetBrightness { brightness } => {
+                if brightness.is_some_and(|b| !(0. ..=1.).contains(&b)) {
+                    return Reply::error("The brightness must be between 0 and 1");
+                }
+                self.brightness = brightness;
+            }
+            ControlCommand::Power { on } => self.blanked = !on,
+            ControlCommand::Quit => self.quit = true,
+        }
+        Reply::ok()
+    }
+
+    fn set_delay(&mut self, delay: Duration) {
+        info!("Delay: {:?}", delay);
+        self.next_image_at = (self.next_image_at + delay).saturating_sub(self.delay);
+        if let Some(remaining) = &mut self.paused {
+            *remaining = (*remaining + delay).saturating_sub(self.delay);
+        }
+        self.delay = delay;
+        self.input_configuration.send(Configuration::Delay(delay));
+    }
+
+    fn set_font_size(&mut self, font_size: f32) {
+        if font_size == self.font_size {
+            return;
+        }
+        self.font_size = font_size;
+        self.input_configuration
+            .send(Configuration::FontSize(font_size));
+        // the space reserved for the status line depends on the font size
+        self.dimensions = None;
+    }
+
+    /// Read the configuration file again and apply the settings that can change while running:
+    /// the status line, delay, font size, scaling, dimming and power schedules. Nothing changes
+    /// if the configuration is invalid, other settings only change on a restart.
+    fn reload(&mut self) -> RahmenResult<()> {
+        let settings = load_settings(self.config_path.as_deref())?;
+        let problems = check_status_line(&settings);
+        if !problems.is_empty() {
+            return Err(config::ConfigError::Message(problems.join("; ")).into());
+        }
+        if let Some(module) = &settings.py_postprocess {
+            reload_python_module(module)?;
+        }
+        let formatter = build_formatter(&settings, &mut self.geocoder)?;
+        info!("Reloading the configuration from {:?}", self.config_path);
+        *self.status_line_formatter.borrow_mut() = formatter;
+        let changed = toml::Table::try_from(&settings).unwrap_or_default();
+        for key in RESTART_SETTINGS {
+            if changed.get(*key) != self.initial_settings.get(*key) {
+                warn!("Restart Rahmen to apply the changed {} setting", key);
+            }
+        }
+        let delay = self.options.time.or(settings.delay).unwrap_or(90.);
+        self.set_delay(Duration::from_secs_f64(delay));
+        self.set_font_size(self.options.font_size.or(settings.font_size).unwrap_or(30.));
+        let (filter, sharpen) = (
+            settings.filter.unwrap_or(ScaleFilter::Triangle),
+            settings.sharpen,
+        );
+        if filter != self.scaling.0 {
+            self.input_configuration.send(Configuration::Filter(filter));
+        }
+        if sharpen != self.scaling.1 {
+            self.input_configuration
+                .send(Configuration::Sharpen(sharpen));
+        }
+        self.scaling = (filter, sharpen);
+        self.dim_schedule = DimSchedule::new(settings.dim.unwrap_or_default());
+        if self.dim_schedule.has_clock_only() && !self.show_clock {
+            warn!("Restart Rahmen to show the clock while dimmed");
+        }
+        // apply the new schedules on the next step
+        self.current_dimming = None;
+        self.input_configuration.send(Configuration::Dim(None));
+        self.power_schedule = PowerSchedule::new(settings.power_off.unwrap_or_default());
+        Ok(())
+    }
+
+    /// Drain the dataflow
+    fn close(mut self) {
+        self.input_configuration.close();
+        while self.worker.step() {}
+    }
+}
+
 fn main() -> RahmenResult<()> {
     env_logger::init();
 
@@ -96,16 +918,41 @@ fn main() -> RahmenResult<()> {
                 .value_parser([
                     #[cfg(feature = "minifb")]
                     "minifb",
+                    #[cfg(feature = "drm")]
+                    "drm",
+                    #[cfg(feature = "terminal")]
+                    "terminal",
+                    "file",
                     "framebuffer",
                 ])
                 .default_value("framebuffer"),
         )
+        .arg(
+            Arg::new("terminal_graphics")
+                .long("terminal_graphics")
+                .help("Graphics protocol of the terminal display, detected by default")
+                .value_parser(["kitty", "sixel", "blocks"]),
+        )
         .arg(Arg::new("input").required(true).index(1))
         .arg(Arg::new("output").short('o').long("output"))
+        .arg(
+            Arg::new("resolution")
+                .long("resolution")
+                .help("Virtual resolution of the file display, e.g. 1920x1080")
+                .value_parser(parse_resolution)
+                .default_value("1920x1080"),
+        )
+        .arg(
+            Arg::new("sequence")
+                .long("sequence")
+                .help("Write each frame of the file display to a new numbered file")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("time")
                 .short('t')
                 .long("time")
+                .global(true)
                 .value_parser(value_parser!(f64)),
         )
         .arg(
@@ -117,23 +964,328 @@ fn main() -> RahmenResult<()> {
         .arg(
             Arg::new("font")
                 .long("font")
+                .global(true)
                 .default_value("/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf"),
         )
         .arg(
             Arg::new("font_size")
                 .long("font_size")
+                .global(true)
                 .value_parser(value_parser!(f32)),
         )
-        .arg(Arg::new("config").long("config").short('c'))
+        .arg(Arg::new("config").long("config").short('c').global(true))
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("config")
+                .about("Inspect the configuration file")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("check")
+                        .about("Check the configuration file for problems")
+                        .arg(Arg::new("path").index(1)),
+                )
+                .subcommand(
+                    Command::new("dump")
+                        .about("Print the effective settings, including command line options"),
+                ),
+        )
+        .subcommand(
+            Command::new("caption")
+                .about("Print the status line for the given images")
+                .arg(Arg::new("files").index(1).num_args(1..).required(true))
+                .arg(
+                    Arg::new("explain")
+                        .long("explain")
+                        .action(ArgAction::SetTrue)
+                        .help("Show the tag values and each processing step"),
+                ),
+        )
+        .subcommand(
+            Command::new("tags")
+                .about("Print all metadata tags of an image, to use in exif_tags")
+                .arg(Arg::new("file").index(1).required(true)),
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        Some(("config", matches)) => return config_command(matches),
+        Some(("caption", matches)) => return caption_command(matches),
+        Some(("tags", matches)) => return tags_command(matches),
+        _ => {}
+    }
+
     // evaluate input arg
     let input = matches
         .get_one::<String>("input")
         .expect("Input missing")
         .as_str();
+    let provider = open_provider(input)?;
+
+    // look for config file
+    let config_path = find_config(matches.get_one::<String>("config"));
+    let mut settings = load_settings(config_path.as_deref())?;
+
+    // continue evaluating the command line args
+    let options = Options {
+        buffer_max_size: *matches
+            .get_one::<usize>("buffer_max_size")
+            .expect("Missing buffer_max_size"),
+        font: matches
+            .get_one::<String>("font")
+            .expect("Missing font")
+            .clone(),
+        time: matches.get_one::<f64>("time").copied(),
+        font_size: matches.get_one::<f32>("font_size").copied(),
+    };
+
+    let blank_path = settings.blank_path.clone();
+    let dither = settings.dither.unwrap_or(false);
+    let rotation = settings.rotate.unwrap_or_default();
+    // commands from control interfaces go to the main slideshow
+    let (request_sender, requests) = std::sync::mpsc::channel();
+
+    // mirror the frames to HTTP clients, if configured
+    #[cfg(feature = "http")]
+    let mut frame_stream = match &settings.http {
+        Some(http) => {
+            let remote = http.remote.unwrap_or(false);
+            let requests = remote.then(|| request_sender.clone());
+            Some(FrameStream::start(&http.listen, http.quality, requests)?)
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "http"))]
+    if settings.http.is_some() {
+        warn!("Ignoring the HTTP server configuration, Rahmen was built without the http feature");
+    }
+
+    // additional outputs mirror the main slideshow, unless they have their own input
+    let mut mirror_displays = vec![];
+    let mut independent = vec![];
+    for output in std::mem::take(&mut settings.outputs) {
+        let display = open_display(&output, &settings)?;
+        if let Some(input) = &output.input {
+            let output_settings = match &output.config {
+                Some(path) => load_settings(Some(Path::new(path)))?,
+                None => settings.clone(),
+            };
+            let output_config_path = output
+                .config
+                .as_ref()
+                .map(PathBuf::from)
+                .or_else(|| config_path.clone());
+            let slideshow = Slideshow::new(
+                output_settings,
+                output_config_path,
+                open_provider(input)?,
+                &options,
+            )?;
+            independent.push((slideshow, display));
+        } else {
+            mirror_displays.push(display);
+        }
+    }
+    let mut mirrors = Mirrors::new(mirror_displays);
+
+    // keys from input devices and displays control the main slideshow
+    let key_map = KeyMap::new(settings.input.as_ref().and_then(|input| input.keys.clone()));
+    let (key_sender, keys) = std::sync::mpsc::channel();
+    #[cfg(feature = "evdev")]
+    if let Some(input) = &settings.input {
+        rahmen::input::read_devices(input.devices.as_deref(), &key_map, key_sender)?;
+    }
+    #[cfg(not(feature = "evdev"))]
+    {
+        if settings
+            .input
+            .as_ref()
+            .is_some_and(|input| input.devices.is_some())
+        {
+            warn!("Ignoring the input devices, Rahmen was built without the evdev feature");
+        }
+        drop(key_sender);
+    }
+
+    #[cfg(unix)]
+    let _control_socket = match &settings.control_socket {
+        Some(path) => Some(ControlSocket::bind(path, request_sender.clone())?),
+        None => None,
+    };
+    #[cfg(not(unix))]
+    if settings.control_socket.is_some() {
+        warn!("Ignoring the control socket, it is only supported on Unix");
+    }
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt) = &settings.mqtt {
+        rahmen::mqtt::start(mqtt, request_sender.clone())?;
+    }
+    #[cfg(not(feature = "mqtt"))]
+    if settings.mqtt.is_some() {
+        warn!("Ignoring the MQTT configuration, Rahmen was built without the mqtt feature");
+    }
+    if let Some(path) = &config_path {
+        if settings.watch_config.unwrap_or(true) {
+            rahmen::watch::watch_config(
+                path.clone(),
+                rahmen::watch::POLL_INTERVAL,
+                request_sender.clone(),
+            );
+        }
+    }
+
+    // quit cleanly on SIGTERM and SIGINT, restoring the console if needed
+    let display_name = matches
+        .get_one::<String>("display")
+        .expect("Display missing")
+        .as_str();
+    #[cfg(unix)]
+    {
+        let restore: fn() = match display_name {
+            "framebuffer" => restore_text_mode,
+            #[cfg(feature = "terminal")]
+            "terminal" => TerminalDisplay::restore,
+            _ => || {},
+        };
+        rahmen::signals::forward_signals(request_sender.clone(), restore)?;
+    }
+    drop(request_sender);
+
+    let mut slideshow = Slideshow::new(settings, config_path, provider, &options)?;
+
+    let mut display_fn = |display: &mut dyn Display| {
+        let pressed = keys.try_iter().chain(display.pressed_keys());
+        for action in pressed
+            .filter_map(|key| key_map.action(&key))
+            .collect::<Vec<_>>()
+        {
+            slideshow.handle(action);
+        }
+        for request in requests.try_iter() {
+            let reply = slideshow.command(request.command.clone());
+            request.reply(reply);
+        }
+        #[cfg(feature = "http")]
+        if let Some(stream) = &mut frame_stream {
+            return slideshow.step(&mut StreamDisplay::new(display, stream));
+        }
+        slideshow.step(display)
+    };
+    let display_fn = |display: &mut dyn Display| {
+        // independent slideshows end on their own, without affecting the others
+        independent.retain_mut(|(slideshow, display)| slideshow.step(display.as_mut()).is_ok());
+        if mirrors.is_empty() {
+            display_fn(display)
+        } else {
+            display_fn(&mut TeeDisplay::new(display, &mut mirrors))
+        }
+    };
+
+    warn_unrotated(display_name, rotation);
+    match display_name {
+        "framebuffer" => {
+            let path_to_device = matches
+                .get_one::<String>("output")
+                .expect("Framebuffer output missing");
+            let framebuffer = framebuffer::Framebuffer::new(path_to_device).unwrap();
+            let _ = framebuffer::Framebuffer::set_kd_mode(framebuffer::KdMode::Graphics)
+                .map_err(|_e| warn!("Failed to set graphics mode."));
+            let power = blank_path
+                .map(SysfsPower::new)
+                .or_else(|| SysfsPower::for_framebuffer(path_to_device));
+            FramebufferDisplay::new(framebuffer, rotation, dither, power)?.main_loop(display_fn);
+            restore_text_mode();
+        }
+        #[cfg(feature = "drm")]
+        "drm" => {
+            let path_to_device = matches
+                .get_one::<String>("output")
+                .map_or("/dev/dri/card0", String::as_str);
+            DrmDisplay::new(path_to_device, rotation)?.main_loop(display_fn);
+        }
+        "file" => {
+            let directory = matches
+                .get_one::<String>("output")
+                .expect("Output directory missing");
+            let resolution = *matches
+                .get_one::<(u32, u32)>("resolution")
+                .expect("Missing resolution");
+            FileDisplay::new(directory, resolution, matches.get_flag("sequence"))?
+                .main_loop(display_fn);
+        }
+        #[cfg(feature = "minifb")]
+        "minifb" => MinifbDisplay::new()?.main_loop(display_fn),
+        #[cfg(feature = "terminal")]
+        "terminal" => {
+            let graphics = matches.get_one::<String>("terminal_graphics").map_or_else(
+                TerminalGraphics::detect,
+                |graphics| {
+                    TerminalGraphics::try_from(graphics.as_str())
+                        .expect("Unknown terminal graphics")
+                },
+            );
+            TerminalDisplay::new(graphics)?.main_loop(display_fn);
+        }
+        _ => panic!("Unknown display"),
+    };
+
+    slideshow.close();
+    for (slideshow, _) in independent {
+        slideshow.close();
+    }
+    Ok(())
+}
+
+/// Build the status line formatter from the settings. The Python search path starts with the
+/// configured paths. `geocoder` holds the place names loaded before, if any, and is replaced when
+/// the `geonames` settings change.
+fn build_formatter(
+    settings: &Settings,
+    geocoder: &mut Option<(GeoNames, Arc<Geocoder>)>,
+) -> RahmenResult<StatusLineFormatter> {
+    // Python search path: use the Python system path, and prepend the value(s) from the config file
+    // Note: contrary to the documentation, the Python system path will not contain the directory from which we're called,
+    // so this has to be indicated in the configuration file
+    if let Some(python_paths) = &settings.py_path {
+        add_python_paths(python_paths)?;
+    }
+
+    // load the place names for the Rahmen.Geo tags, unless they are loaded already
+    match (&settings.geonames, &*geocoder) {
+        (Some(geonames), Some((loaded, _))) if geonames == loaded => {}
+        (Some(geonames), _) => {
+            let places = Geocoder::open(geonames)?;
+            info!("Loaded {} places from {}", places.len(), geonames.cities);
+            *geocoder = Some((geonames.clone(), Arc::new(places)));
+        }
+        (None, _) => *geocoder = None,
+    }
+
+    // build the status line, using the settings from the config file for the individual
+    // metadata tags,
+    // the metadata items being joined using the separator from the config file (or with the
+    // default value (", ") if no separator is given there)
+    StatusLineFormatter::new(
+        settings.status_line.iter().cloned(),
+        settings.py_postprocess.clone(),
+        settings
+            .separator
+            .clone()
+            .unwrap_or_else(|| ", ".to_string()),
+        geocoder.as_ref().map(|(_, places)| Arc::clone(places)),
+    )
+}
+
+/// Switch the console back to text mode after using the framebuffer
+fn restore_text_mode() {
+    let _ = framebuffer::Framebuffer::set_kd_mode(framebuffer::KdMode::Text)
+        .map_err(|_e| warn!("Failed to set text mode."));
+}
+
+/// Open the images to show: `-` for a list of files on stdin, a file with a list of files, or a
+/// file pattern
+fn open_provider(input: &str) -> RahmenResult<Box<dyn Provider<PathBuf>>> {
     // box is used bec of dynamic typing for provider
-    let mut provider: Box<dyn Provider<_>> = if input.eq("-") {
+    Ok(if input.eq("-") {
         info!("Reading from stdin");
         Box::new(ListProvider::new(BufReader::new(std::io::stdin())))
     } else if let Ok(file) = File::open(input) {
@@ -142,13 +1294,14 @@ fn main() -> RahmenResult<()> {
     } else {
         info!("Reading from pattern {}", input);
         Box::new(rahmen::provider_glob::create(input)?)
-    };
+    })
+}
 
-    // look for config file
+/// The configuration file: the given one, or the first found in the XDG config directories or
+/// the system configuration path
+fn find_config(path: Option<&String>) -> Option<PathBuf> {
     let dirs = xdg::BaseDirectories::new();
-    let settings: Settings = if let Some(path) = matches
-        .get_one::<String>("config")
-        .map(PathBuf::from)
+    path.map(PathBuf::from)
         .or_else(|| dirs.find_config_file("rahmen.toml"))
         .or_else(|| {
             #[cfg(unix)]
@@ -159,366 +1312,241 @@ fn main() -> RahmenResult<()> {
             }
             #[cfg(not(unix))]
             None
-        }) {
-        config::Config::builder()
-            .add_source(config::File::from(path))
-            .build()?
-            .try_deserialize()?
-    } else {
-        warn!("Config file not found, continuing with default settings");
-        Default::default()
-    };
-    // Python search path: use the Python system path, and prepend the value(s) from the config file
-    // Note: contrary to the documentation, the Python system path will not contain the directory from which we're called,
-    // so this has to be indicated in the configuration file
-    if let Some(python_paths) = settings.py_path {
-        Python::attach(|py| -> PyResult<()> {
-            let syspath = py.import("sys")?.getattr("path")?.cast_into::<PyList>()?;
-            for path in &python_paths {
-                syspath.insert(0, path)?;
-            }
-            Ok(())
         })
-        .expect("Failed to configure Python sys.path");
-    }
-
-    // build the status line, using the settings from the config file for the individual
-    // metadata tags,
-    // the metadata items being joined using the separator from the config file (or with the
-    // default value (", ") if no separator is given there)
-    let status_line_formatter = StatusLineFormatter::new(
-        settings.status_line.iter().cloned(),
-        settings.py_postprocess,
-        settings.separator.unwrap_or_else(|| ", ".to_string()),
-    )?;
-
-    // continue evaluating the command line args
-    let buffer_max_size: usize = *matches
-        .get_one::<usize>("buffer_max_size")
-        .expect("Missing buffer_max_size");
-
-    let font =
-        Font::from_path(matches.get_one::<String>("font").expect("Missing font"), 0).unwrap();
-    let font_renderer = FontRenderer::with_font(font);
-
-    let duration_millis = (matches
-        .get_one::<f64>("time")
-        .copied()
-        .or(settings.delay)
-        .unwrap_or(90.)
-        * 1000f64) as u64;
-    let delay = Duration::from_millis(duration_millis);
-    info!("Delay: {:?}", delay);
-
-    // font size to use (px)
-    let font_size_f = matches
-        .get_one::<f32>("font_size")
-        .copied()
-        .or(settings.font_size)
-        .unwrap_or(30.);
-
-    let show_time = settings.display_time.unwrap_or(false);
-    let time_format = settings.time_format.unwrap_or("%H:%M:%S".into());
-
-    // initialization for timely dataflow
-    let allocator = timely::communication::allocator::Thread::new();
-    let mut worker = timely::worker::Worker::new(Config::default(), allocator);
-
-    // input: #1 timeline #2 screen resolution
-    let mut input_configuration: InputHandle<_, Configuration> = InputHandle::new();
-    // to gather information about progress
-    let mut probe = ProbeHandle::new();
-
-    let output = worker.dataflow(|scope| {
-        let configuration_stream = input_configuration.to_stream(scope);
-
-        let img_path_stream = scope.scoped::<Product<_, u32>, _, _>("File loading", |inner| {
-            let (handle, cycle) = inner.loop_variable(1);
-            let (ok, err) = configuration_stream
-                .filter(|c| matches!(c, Configuration::Tick))
-                .enter(inner)
-                .concat(&cycle)
-                // obtain next path
-                .map(move |_| fatal_err(provider.next_image()))
-                // Load image
-                .and_then(move |ref path| {
-                    suppress_err(
-                        load_image_from_path(path, Some(buffer_max_size))
-                            .map(|img| (path.clone(), Arc::new(img))),
-                    )
-                })
-                .branch(|_t, d| d.as_ref().err() == Some(&RunControl::Suppressed));
-            err.map(|_| Configuration::Tick).connect_loop(handle);
-            ok.leave()
-        });
-        let err_stream = img_path_stream.err();
-
-        let mut buffer = vec![];
-        let mut stash: HashMap<Duration, String> = HashMap::new();
-        let mut current_text = None;
+}
 
-        let mut status_line_stream = img_path_stream
-            .ok()
-            .flat_map(move |(p, _img)| status_line_formatter.format(&p).ok())
-            .concat(&configuration_stream.flat_map(|c| match c {
-                Configuration::Greeting(text) => Some(text),
-                _ => None,
-            }))
-            .inspect(|loc| info!("Status line: {}", loc));
-        if show_time {
-            status_line_stream = status_line_stream.unary_notify(
-                Pipeline,
-                "Show time",
-                Some(Duration::from_secs(0)),
-                move |input, output, not: &mut Notificator<Duration>| {
-                    input.for_each(|cap, data| {
-                        data.swap(&mut buffer);
-                        if let Some(text) = buffer.drain(..).next_back() {
-                            *stash.entry(*cap.time()).or_default() = text;
-                            not.notify_at(cap.retain());
-                        }
-                    });
-                    not.for_each(|cap, cnt, not| {
-                        let request_notification = if let Some(text) = stash.remove(cap.time()) {
-                            current_text = Some(text);
-                            cnt == 2
-                        } else {
-                            true
-                        };
-                        let now = chrono::Local::now();
-                        let delay = std::cmp::max(50, 1000 - now.timestamp_subsec_millis() as u64);
-                        if request_notification && !not.frontier(0).is_empty() {
-                            let mut next_time = *cap.time() + Duration::from_millis(delay);
-                            while !not.frontier(0).less_equal(&next_time) {
-                                next_time += Duration::from_secs(1);
-                            }
-                            not.notify_at(cap.delayed(&next_time));
-                        }
-                        if let Some(text) = &current_text {
-                            let time_text = format!("[{}] {}", now.format(&time_format), text);
-                            output.session(&cap).give(time_text);
-                        }
-                    });
-                },
+/// Run the `config check` and `config dump` subcommands
+fn config_command(matches: &ArgMatches) -> RahmenResult<()> {
+    match matches.subcommand() {
+        Some(("check", matches)) => {
+            let path = find_config(
+                matches
+                    .get_one::<String>("path")
+                    .or_else(|| matches.get_one::<String>("config")),
+            );
+            let Some(path) = path else {
+                return Err(
+                    config::ConfigError::Message("No configuration file found".into()).into(),
+                );
+            };
+            let (settings, unknown) = read_settings(&path).map_err(|err| {
+                config::ConfigError::Message(format!("{}: {}", path.display(), err))
+            })?;
+            let mut problems = unknown
+                .into_iter()
+                .map(|key| format!("unknown key {:?}", key))
+                .collect::<Vec<_>>();
+            problems.extend(check_settings(&settings));
+            let font = matches.get_one::<String>("font").expect("Missing font");
+            if let Err(err) = Font::from_path(font, 0) {
+                problems.push(format!("font {}: {}", font, err));
+            }
+            if problems.is_empty() {
+                println!("{}: OK", path.display());
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{}: {}", path.display(), problem);
+            }
+            Err(config::ConfigError::Message(format!(
+                "{}: {} problem(s) found",
+                path.display(),
+                problems.len()
+            ))
+            .into())
+        }
+        Some(("dump", matches)) => {
+            let path = find_config(matches.get_one::<String>("config"));
+            let mut settings = load_settings(path.as_deref())?;
+            // command line options take precedence
+            if let Some(time) = matches.get_one::<f64>("time") {
+                settings.delay = Some(*time);
+            }
+            if let Some(font_size) = matches.get_one::<f32>("font_size") {
+                settings.font_size = Some(*font_size);
+            }
+            if let Some(mqtt) = &mut settings.mqtt {
+                if mqtt.password.is_some() {
+                    mqtt.password = Some("********".into());
+                }
+            }
+            if let Some(path) = &path {
+                println!("# {}", path.display());
+            }
+            print!(
+                "{}",
+                toml::to_string(&settings).map_err(std::io::Error::other)?
             );
+            Ok(())
         }
-        let status_line_stream =
-            status_line_stream.map(|s| s.split('\n').map(Into::into).collect());
-
-        let text_img_stream =
-            status_line_stream.format_text(&configuration_stream, font_renderer, 2);
+        _ => unreachable!("subcommand required"),
+    }
+}
 
-        let adjusted_configuration_stream = {
-            let mut stash: HashMap<_, Vec<_>> = HashMap::new();
-            let mut buffer = vec![];
-            // Hack: adjust screen size for the resize operator to reserve space for the status line
-            let mut current_font_size = None;
-            let mut current_font_canvas_vstretch = None;
-
-            configuration_stream.unary_notify(
-                Pipeline,
-                "Adjust configuration",
-                None,
-                move |input, output, not| {
-                    input.for_each(|cap, data| {
-                        data.swap(&mut buffer);
-                        stash.entry(*cap.time()).or_default().append(&mut buffer);
-                        not.notify_at(cap.retain());
-                    });
-                    not.for_each(|cap, _, _not| {
-                        if let Some(updates) = stash.remove(cap.time()) {
-                            output.session(&cap).give_iterator(updates.into_iter().map(
-                                |configuration| {
-                                    match configuration {
-                                        Configuration::FontSize(font_size) => {
-                                            current_font_size = Some(font_size);
-                                            Configuration::FontSize(font_size)
-                                        }
-                                        Configuration::FontCanvasVStretch(font_canvas_vstretch) => {
-                                            current_font_canvas_vstretch =
-                                                Some(font_canvas_vstretch);
-                                            Configuration::FontCanvasVStretch(font_canvas_vstretch)
-                                        }
-                                        Configuration::ScreenDimensions(width, height) => {
-                                            Configuration::ScreenDimensions(
-                                                width,
-                                                height
-                                                    - (current_font_size.unwrap_or(0.)
-                                                        * current_font_canvas_vstretch
-                                                            .unwrap_or(1.0))
-                                                    .ceil()
-                                                        as u32,
-                                            )
-                                        }
-                                        configuration => configuration,
-                                    }
-                                },
-                            ));
-                        }
-                    });
-                },
-            )
+/// Run the `caption` subcommand
+fn caption_command(matches: &ArgMatches) -> RahmenResult<()> {
+    let settings = load_settings(find_config(matches.get_one::<String>("config")).as_deref())?;
+    let formatter = build_formatter(&settings, &mut None)?;
+    let explain = matches.get_flag("explain");
+    for file in matches.get_many::<String>("files").expect("Missing files") {
+        if !explain {
+            match formatter.format(file) {
+                Ok(line) => println!("{}: {}", file, line),
+                Err(err) => println!("{}: {}", file, err),
+            }
+            continue;
+        }
+        let explanation = match formatter.explain(file) {
+            Ok(explanation) => explanation,
+            Err(err) => {
+                println!("{}: {}", file, err);
+                continue;
+            }
         };
-
-        let img_stream = img_path_stream
-            .ok()
-            .map(|(_, img)| img)
-            .concat(&configuration_stream.flat_map(|c| match c {
-                Configuration::Splash(img) => Some(img),
-                _ => None,
-            }))
-            .resize_image(&adjusted_configuration_stream, 1);
-
-        let mut size_stash: HashMap<usize, _> = HashMap::new();
-        let mut input_buffer: HashMap<_, Vec<(_, _, _)>> = HashMap::new();
-
-        let composed_img_stream = img_stream.concat(&text_img_stream).unary_notify(
-            Pipeline,
-            "Infer blanking",
-            None,
-            move |input, output, not| {
-                let mut buffer = vec![];
-                input.for_each(|time, data| {
-                    data.swap(&mut buffer);
-                    input_buffer
-                        .entry(*time.time())
-                        .or_default()
-                        .append(&mut buffer);
-                    not.notify_at(time.retain());
-                });
-                not.for_each(|time, _count, _not| {
-                    if let Some(updates) = input_buffer.remove(time.time()) {
-                        output
-                            .session(&time)
-                            .give_iterator(updates.into_iter().flat_map(|(key, anchor, img)| {
-                                let rect = RectI::new(
-                                    anchor,
-                                    Vector::new(img.dimensions().0 as _, img.dimensions().1 as _),
-                                );
-                                size_stash
-                                    .insert(key, rect)
-                                    .into_iter()
-                                    .flat_map(move |old_rect| compute_blanking(key, rect, old_rect))
-                                    .chain(Some(Render::Image(key, anchor, img)))
-                            }));
-                    }
-                })
-            },
-        );
-
-        err_stream
-            .map(Err)
-            .concat(&composed_img_stream.map(Ok))
-            .probe_with(&mut probe)
-            .capture()
-    });
-
-    let start_time = Instant::now();
-    let mut dimensions = None;
-
-    input_configuration.send(Configuration::FontSize(font_size_f));
-    // enlarge font canvas vertically by this factor (default given here: 1.4)
-    input_configuration.send(Configuration::FontCanvasVStretch(1.4));
-    match image::load_from_memory(SPLASH) {
-        Ok(image) => {
-            info!("Sending splash screen");
-            input_configuration.send(Configuration::Splash(Arc::new(image)));
+        println!("{}", file);
+        for (index, element) in explanation.elements.iter().enumerate() {
+            println!("  status_line[{}]", index);
+            for (tag, value) in &element.tags {
+                let matched = if element.matched.as_ref() == Some(tag) {
+                    " (used)"
+                } else {
+                    ""
+                };
+                match value {
+                    Some(value) => println!("    {} = {:?}{}", tag, value, matched),
+                    None => println!("    {} missing", tag),
+                }
+            }
+            for (step, value) in &element.steps {
+                println!("    {} -> {:?}", step, value);
+            }
         }
-        Err(err) => warn!("Failed to load splash screen: {}", err),
+        if let Some(input) = &explanation.postprocess_input {
+            println!("  before postprocessing: {:?}", input);
+            println!("  after postprocessing: {:?}", explanation.elements_out);
+        } else {
+            println!("  elements: {:?}", explanation.elements_out);
+        }
+        println!("  status line: {:?}", explanation.line);
     }
-    input_configuration.send(Configuration::Greeting(format!("Rahmen {}", VERSION)));
-
-    let mut next_image_at = start_time.elapsed() + Duration::from_secs(1);
-
-    let display_fn = |display: &mut dyn Display| {
-        let now = start_time.elapsed();
+    Ok(())
+}
 
-        if next_image_at < now {
-            input_configuration.send(Configuration::Tick);
-            next_image_at = now + delay;
+/// Run the `tags` subcommand
+fn tags_command(matches: &ArgMatches) -> RahmenResult<()> {
+    /// Shorten long values, e.g. maker notes, to keep one line per tag
+    fn shorten(value: &str) -> String {
+        const MAX_CHARS: usize = 80;
+        if value.chars().count() > MAX_CHARS {
+            format!("{}…", value.chars().take(MAX_CHARS).collect::<String>())
+        } else {
+            value.to_string()
         }
+    }
 
-        if Some(display.dimensions()) != dimensions {
-            dimensions = Some(display.dimensions());
-            input_configuration.send(Configuration::ScreenDimensions(
-                display.dimensions().0,
-                display.dimensions().1,
-            ));
+    let file = matches.get_one::<String>("file").expect("Missing file");
+    let metadata = rahmen::exiv2::Metadata::new_from_path(file)?;
+    // the Rahmen.Geo tags need the place names from the configuration
+    let settings = load_settings(find_config(matches.get_one::<String>("config")).as_deref())?;
+    let geocoder = settings.geonames.as_ref().map(Geocoder::open).transpose()?;
+    for datum in metadata.iter()? {
+        print!(
+            "{:<45} {:<10} {:>5}  {}",
+            datum.key,
+            datum.type_name,
+            datum.count,
+            shorten(&datum.interpreted)
+        );
+        if datum.raw != datum.interpreted {
+            print!(" ({})", shorten(&datum.raw));
         }
-        input_configuration.advance_to(now);
-        while probe.less_than(&now) {
-            worker.step();
+        println!();
+    }
+    for key in tags::VIRTUAL_TAGS {
+        if let Some(value) =
+            tags::read_virtual(&metadata, key, Default::default(), geocoder.as_ref())
+        {
+            println!("{:<45} {:<10} {:>5}  {}", key, "Virtual", "", value);
         }
-        let mut has_update = false;
-        let result = match output.try_iter().all(|result| match result {
-            // Continue processing on progress messages
-            Event::Progress(_) => true,
-            // Handle data messages by rending an image and determining whether to terminate
-            Event::Messages(_, r) => {
-                let mut terminate = false;
-                for result in r {
-                    match result {
-                        Ok(Render::Image(key, anchor, ref img)) => {
-                            has_update = true;
-                            if let Err(err) = display.render(key, anchor, img.as_ref()) {
-                                error!("Render failed: {}", err);
-                                terminate = true;
-                            }
-                        }
-                        Ok(Render::Blank(key, anchor, size)) => {
-                            has_update = true;
-                            if let Err(err) = display.blank(key, anchor, size) {
-                                error!("Blank failed: {}", err);
-                                terminate = true;
-                            }
-                        }
-                        Err(RunControl::Terminate) => terminate = true,
-                        _ => {}
-                    }
-                }
-                !terminate
-            }
-        }) {
-            true => Ok(()),
-            false => Err(RahmenError::Terminate),
-        };
-        if result.is_ok() && has_update {
-            display.update()
-        } else {
-            result
+    }
+    Ok(())
+}
+
+/// Load the settings from a configuration file, or use the defaults without one
+fn load_settings(path: Option<&Path>) -> RahmenResult<Settings> {
+    if let Some(path) = path {
+        let (settings, unknown) = read_settings(path)?;
+        for key in unknown {
+            warn!("Ignoring unknown configuration key {:?} in {:?}", key, path);
         }
-    };
+        Ok(settings)
+    } else {
+        warn!("Config file not found, continuing with default settings");
+        Ok(Default::default())
+    }
+}
 
-    match matches
-        .get_one::<String>("display")
-        .expect("Display missing")
-        .as_str()
-    {
+/// Open the display of an additional output, using the display settings of the main output
+fn open_display(output: &Output, settings: &Settings) -> RahmenResult<Box<dyn Display>> {
+    let rotation = settings.rotate.unwrap_or_default();
+    let device = output.device.as_deref();
+    info!("Opening {} output {:?}", output.display, device);
+    warn_unrotated(&output.display, rotation);
+    Ok(match output.display.as_str() {
         "framebuffer" => {
-            let path_to_device = matches
-                .get_one::<String>("output")
-                .expect("Framebuffer output missing");
-            let framebuffer = framebuffer::Framebuffer::new(path_to_device).unwrap();
-            let _ = framebuffer::Framebuffer::set_kd_mode(framebuffer::KdMode::Graphics)
-                .map_err(|_e| warn!("Failed to set graphics mode."));
-            ctrlc::set_handler(|| {
-                let _ = framebuffer::Framebuffer::set_kd_mode(framebuffer::KdMode::Text)
-                    .map_err(|_e| warn!("Failed to set graphics mode."));
-                std::process::exit(0);
-            })
-            .unwrap();
-            FramebufferDisplay::new(framebuffer).main_loop(display_fn);
-            let _ = framebuffer::Framebuffer::set_kd_mode(framebuffer::KdMode::Text)
-                .map_err(|_e| warn!("Failed to set graphics mode."));
+            let device = device.unwrap_or("/dev/fb1");
+            let framebuffer = framebuffer::Framebuffer::new(device)
+                .map_err(|err| std::io::Error::other(err.to_string()))?;
+            let power = SysfsPower::for_framebuffer(device);
+            Box::new(FramebufferDisplay::new(
+                framebuffer,
+                rotation,
+                settings.dither.unwrap_or(false),
+                power,
+            )?)
         }
+        #[cfg(feature = "drm")]
+        "drm" => Box::new(DrmDisplay::new(
+            device.unwrap_or("/dev/dri/card1"),
+            rotation,
+        )?),
+        "file" => Box::new(FileDisplay::new(
+            device.ok_or_else(|| std::io::Error::other("Output directory missing"))?,
+            output.resolution.unwrap_or((1920, 1080)),
+            false,
+        )?),
         #[cfg(feature = "minifb")]
-        "minifb" => MinifbDisplay::new()?.main_loop(display_fn),
-        _ => panic!("Unknown display"),
-    };
+        "minifb" => Box::new(MinifbDisplay::new()?),
+        display => {
+            return Err(
+                std::io::Error::other(format!("Unknown output display: {}", display)).into(),
+            );
+        }
+    })
+}
 
-    input_configuration.close();
-    while worker.step() {}
-    Ok(())
+/// Warn if the content should be rotated on a display which does not rotate it: only the
+/// framebuffer and DRM displays do
+fn warn_unrotated(display: &str, rotation: Rotation) {
+    if rotation != Rotation::None && !matches!(display, "framebuffer" | "drm") {
+        warn!(
+            "Ignoring rotate = {} for the {} display",
+            u32::from(rotation),
+            display
+        );
+    }
+}
+
+/// Parse a resolution of the form `WIDTHxHEIGHT`
+fn parse_resolution(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("Expected WIDTHxHEIGHT, got {}", value))?;
+    let parse = |v: &str| match v.trim().parse::<u32>() {
+        Ok(v) if v > 0 => Ok(v),
+        _ => Err(format!("Invalid dimension: {}", v)),
+    };
+    Ok((parse(width)?, parse(height)?))
 }
 
 fn compute_blanking(key: usize, rect: RectI, old_rect: RectI) -> Vec<Render> {
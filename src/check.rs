@@ -0,0 +1,122 @@
+//! Validate a configuration before running with it.
+//!
+//! Mistakes in the configuration file otherwise only show up while the frame is running, or are
+//! silently ignored like unknown keys. The checks here collect all problems at once.
+
+use std::path::Path;
+
+use chrono::format::{Item, StrftimeItems};
+use regex::Regex;
+
+use crate::config::Settings;
+use crate::errors::RahmenResult;
+use crate::geocode::Geocoder;
+use crate::mat::Passepartout;
+use crate::provider::{StatusLineFormatter, add_python_paths, str_to_case};
+use crate::tags;
+
+/// Read the settings from a configuration file, and the keys in the file which are not settings
+pub fn read_settings(path: &Path) -> RahmenResult<(Settings, Vec<String>)> {
+    let config = config::Config::builder()
+        .add_source(config::File::from(path))
+        .build()?;
+    let mut unknown = vec![];
+    let settings = serde_ignored::deserialize(config, |key| unknown.push(key.to_string()))?;
+    Ok((settings, unknown))
+}
+
+/// Whether `key` looks like an exiv2 key, e.g. `Exif.Photo.DateTimeOriginal`,
+/// `Iptc.Application2.City` or `Xmp.dc.subject`, or is a virtual tag like `Rahmen.GPS.Position`
+pub fn is_valid_key(key: &str) -> bool {
+    if tags::is_virtual(key) {
+        return true;
+    }
+    let mut parts = key.splitn(3, '.');
+    let family = parts.next().unwrap_or_default();
+    matches!(family, "Exif" | "Iptc" | "Xmp")
+        && parts.next().is_some_and(|group| {
+            !group.is_empty() && group.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        && parts.next().is_some_and(|tag| !tag.is_empty())
+}
+
+/// Check the status line elements for invalid metadata keys, regular expressions and cases,
+/// without loading any files. Returns a description of each problem.
+pub fn check_status_line(settings: &Settings) -> Vec<String> {
+    let mut problems = vec![];
+    for (index, element) in settings.status_line.iter().enumerate() {
+        let name = format!("status_line[{}]", index);
+        if element.exif_tags.is_empty() {
+            problems.push(format!("{}: no exif_tags", name));
+        }
+        for key in &element.exif_tags {
+            if !is_valid_key(key) {
+                problems.push(format!("{}: invalid metadata key {:?}", name, key));
+            } else if tags::needs_geocoder(key) && settings.geonames.is_none() {
+                problems.push(format!("{}: {:?} needs [geonames]", name, key));
+            }
+        }
+        for replacement in element.replace.iter().flatten() {
+            if let Err(err) = Regex::new(&replacement.regex) {
+                problems.push(format!("{}: invalid regex: {}", name, err));
+            }
+        }
+        if let Some(case_conversion) = &element.case_conversion {
+            for case in [&case_conversion.from, &case_conversion.to] {
+                if let Err(err) = str_to_case(case.clone()) {
+                    problems.push(format!("{}: {}", name, err));
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Check the settings for problems which only show up when running, e.g. invalid regular
+/// expressions, unknown cases, or a failing Python module. Returns a description of each problem.
+pub fn check_settings(settings: &Settings) -> Vec<String> {
+    let mut problems = check_status_line(settings);
+    if let Some(module) = &settings.py_postprocess {
+        let python =
+            add_python_paths(settings.py_path.as_deref().unwrap_or_default()).and_then(|()| {
+                StatusLineFormatter::new(
+                    std::iter::empty(),
+                    Some(module.clone()),
+                    String::new(),
+                    None,
+                )
+            });
+        if let Err(err) = python {
+            problems.push(format!(
+                "py_postprocess: module {:?} failed: {}",
+                module, err
+            ));
+        }
+    }
+    if let Some(time_format) = &settings.time_format {
+        if StrftimeItems::new(time_format).any(|item| matches!(item, Item::Error)) {
+            problems.push(format!("time_format: invalid format {:?}", time_format));
+        }
+    }
+    if let Some(geonames) = &settings.geonames {
+        if let Err(err) = Geocoder::open(geonames) {
+            problems.push(format!("geonames: {}", err));
+        }
+    }
+    if let Err(err) = Passepartout::new(settings.mat.clone(), settings.border) {
+        problems.push(format!("mat: {}", err));
+    }
+    for (index, output) in settings.outputs.iter().enumerate() {
+        if let Some(path) = &output.config {
+            match read_settings(Path::new(path)) {
+                Ok((output_settings, _)) => problems.extend(
+                    check_settings(&output_settings)
+                        .into_iter()
+                        .map(|problem| format!("output[{}] {}: {}", index, path, problem)),
+                ),
+                Err(err) => problems.push(format!("output[{}]: {}: {}", index, path, err)),
+            }
+        }
+    }
+    problems
+}
@@ -1,9 +1,12 @@
 //! Configuration data for Rahmen
 
-use serde::Deserialize;
+use std::collections::HashMap;
+
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
 
 /// An element of the status line
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Element {
     /// Capitalize the words in the tag (optional)
     pub capitalize: Option<bool>,
@@ -13,10 +16,24 @@ pub struct Element {
     pub replace: Option<Vec<Replacement>>,
     /// Optional case conversion instruction
     pub case_conversion: Option<CaseConversion>,
+    /// Format of the coordinates in the `Rahmen.GPS` tags (optional, default degrees, minutes and
+    /// seconds)
+    pub gps_format: Option<GpsFormat>,
+}
+
+/// How to show GPS coordinates
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum GpsFormat {
+    /// Degrees, minutes and seconds, e.g. `33°26'52.8"S 70°40'12.0"W`
+    #[default]
+    Dms,
+    /// Signed decimal degrees, e.g. `-33.44800, -70.67000`
+    Decimal,
 }
 
 /// case conversion
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CaseConversion {
     /// from case
     pub from: String,
@@ -25,7 +42,7 @@ pub struct CaseConversion {
 }
 
 /// replacement regular expression and value
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Replacement {
     /// the regular expression to use
     pub regex: String,
@@ -34,7 +51,7 @@ pub struct Replacement {
 }
 
 /// Config file root structure
-#[derive(Debug, Default, Deserialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 // this is called in rahmen.rs where a new status line formatter is constructed
 pub struct Settings {
     /// Transition delay between images (optional)
@@ -54,4 +71,380 @@ pub struct Settings {
     pub display_time: Option<bool>,
     /// Time format string
     pub time_format: Option<String>,
+    /// Slowly pan and zoom across each image (optional)
+    pub ken_burns: Option<KenBurns>,
+    /// Resampling filter to scale images (optional, default triangle)
+    pub filter: Option<Filter>,
+    /// Unsharp mask to apply after downscaling (optional)
+    pub sharpen: Option<Sharpen>,
+    /// Clockwise rotation of the screen content in degrees: 0, 90, 180 or 270 (optional)
+    pub rotate: Option<Rotation>,
+    /// Outer margins of the screen (optional)
+    pub margins: Option<Margins>,
+    /// Mat around the image (optional)
+    pub mat: Option<Mat>,
+    /// Border around the image (optional)
+    pub border: Option<Border>,
+    /// Daily time windows during which the screen is dimmed (optional)
+    pub dim: Option<Vec<Dim>>,
+    /// Daily time windows during which the screen is powered off (optional)
+    pub power_off: Option<Vec<PowerOff>>,
+    /// Sysfs attribute to power the screen on and off (optional, derived from the framebuffer
+    /// device by default)
+    pub blank_path: Option<String>,
+    /// Dither colors on framebuffers with less than 8 bits per channel (optional, default false)
+    pub dither: Option<bool>,
+    /// Embedded HTTP server (optional, requires the `http` feature)
+    pub http: Option<Http>,
+    /// Additional outputs (optional)
+    #[serde(default, rename = "output")]
+    pub outputs: Vec<Output>,
+    /// Keyboard and remote control input (optional)
+    pub input: Option<Input>,
+    /// Unix domain socket accepting commands (optional)
+    pub control_socket: Option<String>,
+    /// MQTT broker to publish the state to and receive commands from (optional)
+    pub mqtt: Option<Mqtt>,
+    /// Reload the configuration file when it changes (optional, default true)
+    pub watch_config: Option<bool>,
+    /// GeoNames data for the `Rahmen.Geo` tags (optional)
+    pub geonames: Option<GeoNames>,
+}
+
+/// GeoNames data to look up place names for GPS positions offline, see
+/// <https://download.geonames.org/export/dump/>
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct GeoNames {
+    /// Path to a cities file, e.g. `cities15000.txt`
+    pub cities: String,
+    /// Path to `admin1CodesASCII.txt` for region names (optional, region codes without)
+    pub admin1: Option<String>,
+    /// Path to `countryInfo.txt` for country names (optional, country codes without)
+    pub countries: Option<String>,
+    /// Maximum distance to the nearest city in km (optional, default 50)
+    pub max_distance: Option<f64>,
+}
+
+/// Point of interest to pan and zoom towards
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Focus {
+    /// Choose start and end of the motion at random
+    Random,
+    /// Zoom towards the center of the image
+    Center,
+    /// Zoom towards the EXIF subject area, falling back to the center if it is absent
+    Subject,
+}
+
+/// Ken Burns pan-and-zoom effect settings
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy)]
+pub struct KenBurns {
+    /// Maximum zoom factor relative to the screen-filling image (optional, default 1.2)
+    pub zoom: Option<f32>,
+    /// Point of interest (optional, default random)
+    pub focus: Option<Focus>,
+    /// Number of frames rendered per second (optional, default 10)
+    pub fps: Option<f32>,
+}
+
+/// Resampling filter to scale images, from fastest to best quality
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    /// Nearest neighbor
+    Nearest,
+    /// Linear
+    Triangle,
+    /// Cubic
+    CatmullRom,
+    /// Gaussian
+    Gaussian,
+    /// Lanczos with window 3
+    Lanczos3,
+}
+
+impl From<Filter> for image::imageops::FilterType {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => Self::Nearest,
+            Filter::Triangle => Self::Triangle,
+            Filter::CatmullRom => Self::CatmullRom,
+            Filter::Gaussian => Self::Gaussian,
+            Filter::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// Unsharp mask parameters
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Sharpen {
+    /// Amount of blur of the mask
+    pub sigma: f32,
+    /// Minimum brightness change to sharpen
+    pub threshold: i32,
+}
+
+/// Clockwise rotation of the screen content, for example for portrait-mounted monitors
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "u32", into = "u32")]
+pub enum Rotation {
+    /// No rotation
+    #[default]
+    None,
+    /// Rotate by 90 degrees
+    Cw90,
+    /// Rotate by 180 degrees
+    Cw180,
+    /// Rotate by 270 degrees
+    Cw270,
+}
+
+impl Rotation {
+    /// Returns true if the rotation swaps width and height
+    pub fn is_transposed(self) -> bool {
+        matches!(self, Rotation::Cw90 | Rotation::Cw270)
+    }
+
+    /// Map the point `(x, y)` of an image with `dimensions` to its position after rotation
+    pub fn map(self, (x, y): (u32, u32), (width, height): (u32, u32)) -> (u32, u32) {
+        match self {
+            Rotation::None => (x, y),
+            Rotation::Cw90 => (height - 1 - y, x),
+            Rotation::Cw180 => (width - 1 - x, height - 1 - y),
+            Rotation::Cw270 => (y, width - 1 - x),
+        }
+    }
+}
+
+impl From<Rotation> for u32 {
+    fn from(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::None => 0,
+            Rotation::Cw90 => 90,
+            Rotation::Cw180 => 180,
+            Rotation::Cw270 => 270,
+        }
+    }
+}
+
+impl TryFrom<u32> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u32) -> Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Rotation::None),
+            90 => Ok(Rotation::Cw90),
+            180 => Ok(Rotation::Cw180),
+            270 => Ok(Rotation::Cw270),
+            _ => Err(format!(
+                "Unsupported rotation: {}, expected 0, 90, 180 or 270",
+                degrees
+            )),
+        }
+    }
+}
+
+/// An RGB color, written as `"#rrggbb"` in the configuration file
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color(pub [u8; 3]);
+
+impl From<Color> for String {
+    fn from(Color([r, g, b]): Color) -> Self {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("Invalid color: {}, expected \"#rrggbb\"", value))
+        };
+        // `from_str_radix` would also accept a sign
+        if hex.len() != 6 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid color: {}, expected \"#rrggbb\"", value));
+        }
+        Ok(Color([channel(0)?, channel(2)?, channel(4)?]))
+    }
+}
+
+/// Outer margins in pixels, for example for TVs cutting off the edges of the picture (overscan)
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy)]
+pub struct Margins {
+    /// Top margin (optional)
+    pub top: Option<u32>,
+    /// Right margin (optional)
+    pub right: Option<u32>,
+    /// Bottom margin (optional)
+    pub bottom: Option<u32>,
+    /// Left margin (optional)
+    pub left: Option<u32>,
+}
+
+impl Margins {
+    /// Sum of the left and right margins
+    pub fn horizontal(&self) -> u32 {
+        self.left.unwrap_or(0) + self.right.unwrap_or(0)
+    }
+
+    /// Sum of the top and bottom margins
+    pub fn vertical(&self) -> u32 {
+        self.top.unwrap_or(0) + self.bottom.unwrap_or(0)
+    }
+}
+
+/// Mat (passe-partout) filling the space around the image
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct Mat {
+    /// Minimum width of the mat around the image in pixels (optional, default 0)
+    pub width: Option<u32>,
+    /// Color of the mat (optional, default off-white)
+    pub color: Option<Color>,
+    /// Path to an image tiled across the mat, takes precedence over the color (optional)
+    pub texture: Option<String>,
+}
+
+/// Thin border or drop shadow around the image
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy)]
+pub struct Border {
+    /// Width of the border in pixels (optional, default 0)
+    pub width: Option<u32>,
+    /// Color of the border (optional, default black)
+    pub color: Option<Color>,
+    /// Offset of the drop shadow to the lower right in pixels (optional, default no shadow)
+    pub shadow: Option<u32>,
+}
+
+/// A time of day, written as `"HH:MM"` in the configuration file
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(pub NaiveTime);
+
+impl From<TimeOfDay> for String {
+    fn from(TimeOfDay(time): TimeOfDay) -> Self {
+        if time.second() == 0 {
+            time.format("%H:%M").to_string()
+        } else {
+            time.format("%H:%M:%S").to_string()
+        }
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        NaiveTime::parse_from_str(&value, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(&value, "%H:%M:%S"))
+            .map(TimeOfDay)
+            .map_err(|e| format!("Invalid time of day: {}, expected \"HH:MM\": {}", value, e))
+    }
+}
+
+/// Dim the screen during a daily time window
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct Dim {
+    /// Start of the time window
+    pub from: TimeOfDay,
+    /// End of the time window, may be earlier than `from` to span midnight
+    pub to: TimeOfDay,
+    /// Brightness factor from 0 to 1 (optional, default 0.3)
+    pub brightness: Option<f32>,
+    /// Shift the colors to a warm color temperature (optional, default false)
+    pub warm: Option<bool>,
+    /// Hide the image and only show the clock (optional, default false)
+    pub clock_only: Option<bool>,
+}
+
+/// Power off the screen during a daily time window
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct PowerOff {
+    /// Start of the time window
+    pub from: TimeOfDay,
+    /// End of the time window, may be earlier than `from` to span midnight
+    pub to: TimeOfDay,
+}
+
+/// Embedded HTTP server
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Http {
+    /// Address and port to listen on, e.g. "0.0.0.0:8080"
+    pub listen: String,
+    /// JPEG quality of the frame stream from 1 to 100 (optional, default 80)
+    pub quality: Option<f32>,
+    /// Serve the REST API and the web remote control (optional, default false)
+    pub remote: Option<bool>,
+}
+
+/// An additional output, mirroring the main slideshow or showing its own
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Output {
+    /// Display provider, e.g. "framebuffer"
+    pub display: String,
+    /// Device or directory of the display provider (optional, default "/dev/fb1" or
+    /// "/dev/dri/card1", the device after the one of the main display)
+    pub device: Option<String>,
+    /// Virtual resolution of the file display provider (optional, default 1920x1080)
+    pub resolution: Option<(u32, u32)>,
+    /// Input of an independent slideshow: a file name, pattern or list of files (optional,
+    /// mirrors the main slideshow by default)
+    pub input: Option<String>,
+    /// Configuration file of the independent slideshow (optional, default: the same
+    /// configuration)
+    pub config: Option<String>,
+}
+
+/// An action triggered by a key
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Show the next image
+    Next,
+    /// Show the previous image
+    Previous,
+    /// Pause or resume the slideshow
+    Pause,
+    /// Hide or show the status line
+    StatusLine,
+    /// Show the path of the image instead of the status line, or switch back
+    Info,
+    /// Blank the screen, or switch it back on
+    Blank,
+}
+
+/// Keyboard and remote control input
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct Input {
+    /// Input devices to read, e.g. "/dev/input/event0" (optional, default: all devices with any
+    /// of the mapped keys)
+    pub devices: Option<Vec<String>>,
+    /// Actions by key name, e.g. `KEY_RIGHT = "next"` (optional, replaces the default mapping)
+    pub keys: Option<HashMap<String, Action>>,
+}
+
+/// Connection to an MQTT broker
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Mqtt {
+    /// Host name of the broker
+    pub host: String,
+    /// Port of the broker (optional, default 1883)
+    pub port: Option<u16>,
+    /// Client id, also used to identify the device in Home Assistant (optional, default "rahmen")
+    pub client_id: Option<String>,
+    /// User name (optional)
+    pub username: Option<String>,
+    /// Password (optional)
+    pub password: Option<String>,
+    /// Prefix of the state and command topics (optional, default "rahmen")
+    pub topic: Option<String>,
+    /// Publish Home Assistant discovery payloads (optional, default true)
+    pub discovery: Option<bool>,
+    /// Prefix of the Home Assistant discovery topics (optional, default "homeassistant")
+    pub discovery_prefix: Option<String>,
 }
@@ -0,0 +1,240 @@
+//! Control a running slideshow with commands, e.g. from other local services.
+//!
+//! Commands are JSON objects with a `command` field, like `{"command": "goto", "path": "a.jpg"}`.
+//! Every command is answered with a [`Reply`]. Command sources send [`Request`]s over a channel,
+//! which the display loop handles one after the other, so commands are ordered with all other
+//! configuration updates of the dataflow.
+//!
+//! On Unix, a [`ControlSocket`] accepts line-delimited commands on a Unix domain socket.
+
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use serde::{Deserialize, Serialize};
+
+/// A command to control a slideshow
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    /// Show the next image
+    Next,
+    /// Go back to the previous image
+    Previous,
+    /// Stop advancing the slides
+    Pause,
+    /// Continue advancing the slides
+    Resume,
+    /// Show a specific image
+    Goto {
+        /// Path of the image
+        path: String,
+    },
+    /// Change the time each image is shown
+    SetDelay {
+        /// Delay in seconds
+        seconds: f64,
+    },
+    /// Change the font size of the status line
+    SetFontSize {
+        /// Font size in pixels
+        size: f32,
+    },
+    /// Show a message in the status line until the next image
+    ShowMessage {
+        /// Text of the message
+        text: String,
+    },
+    /// Reload the configuration file
+    ReloadConfig,
+    /// Change the brightness of the screen
+    SetBrightness {
+        /// Brightness from 0 to 1, or `None` to follow the dimming schedule again
+        #[serde(default)]
+        brightness: Option<f32>,
+    },
+    /// Switch the screen on or off
+    Power {
+        /// Whether the screen should be on
+        on: bool,
+    },
+    /// Query the state of the slideshow
+    Status,
+    /// Terminate the slideshow
+    Quit,
+}
+
+/// State of a slideshow, as answered to status queries
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Status {
+    /// Path of the current image, if any
+    pub path: Option<String>,
+    /// Index of the current image, counting all images shown since the start
+    pub index: usize,
+    /// Status line of the current image, if any
+    pub caption: Option<String>,
+    /// Time until the next image in seconds, frozen while paused
+    pub remaining: f64,
+    /// Whether the slideshow is paused
+    pub paused: bool,
+    /// Whether the screen is on
+    pub powered: bool,
+    /// Brightness of the screen from 0 to 1
+    pub brightness: f32,
+}
+
+/// Answer to a command
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reply {
+    /// Whether the command succeeded
+    pub ok: bool,
+    /// Reason of the failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// State of the slideshow, for status queries
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+}
+
+impl Reply {
+    /// A reply to a successful command
+    pub fn ok() -> Self {
+        Self {
+            ok: true,
+            error: None,
+            status: None,
+        }
+    }
+
+    /// A reply to a failed command
+    pub fn error<E: ToString>(error: E) -> Self {
+        Self {
+            ok: false,
+            error: Some(error.to_string()),
+            status: None,
+        }
+    }
+
+    /// A reply to a status query
+    pub fn status(status: Status) -> Self {
+        Self {
+            ok: true,
+            error: None,
+            status: Some(status),
+        }
+    }
+}
+
+/// A command waiting to be handled by the display loop
+#[derive(Debug)]
+pub struct Request {
+    /// The command to handle
+    pub command: Command,
+    reply: Sender<Reply>,
+}
+
+impl Request {
+    /// Create a request for `command`, and the receiver of its reply
+    pub fn new(command: Command) -> (Self, Receiver<Reply>) {
+        let (reply, receiver) = channel();
+        (Self { command, reply }, receiver)
+    }
+
+    /// Answer the request. The sender may have given up waiting, which is ignored.
+    pub fn reply(self, reply: Reply) {
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// Parse a command and send it to the display loop, waiting for the reply
+pub fn submit(line: &str, requests: &Sender<Request>) -> Reply {
+    match serde_json::from_str(line) {
+        Ok(command) => submit_command(command, requests),
+        Err(err) => Reply::error(format!("Invalid command: {}", err)),
+    }
+}
+
+/// Send a command to the display loop and wait for the reply
+pub fn submit_command(command: Command, requests: &Sender<Request>) -> Reply {
+    let (request, reply) = Request::new(command);
+    if requests.send(request).is_err() {
+        return Reply::error("Slideshow terminated");
+    }
+    reply
+        .recv()
+        .unwrap_or_else(|_| Reply::error("Slideshow terminated"))
+}
+
+#[cfg(unix)]
+pub use socket::ControlSocket;
+
+#[cfg(unix)]
+mod socket {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::Sender;
+
+    use super::{Request, submit};
+    use crate::errors::RahmenResult;
+
+    /// A Unix domain socket accepting line-delimited JSON commands, answering each with a line
+    /// of JSON. The socket file is removed when dropped.
+    #[derive(Debug)]
+    pub struct ControlSocket {
+        path: PathBuf,
+    }
+
+    impl ControlSocket {
+        /// Listen on `path` in a background thread, sending the commands to `requests`. A stale
+        /// socket file from a previous run is replaced. Only the owner may connect to the
+        /// socket.
+        pub fn bind<P: AsRef<Path>>(path: P, requests: Sender<Request>) -> RahmenResult<Self> {
+            let path = path.as_ref().to_path_buf();
+            if std::fs::symlink_metadata(&path).is_ok_and(|metadata| {
+                std::os::unix::fs::FileTypeExt::is_socket(&metadata.file_type())
+            }) {
+                std::fs::remove_file(&path)?;
+            }
+            let listener = UnixListener::bind(&path)?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            info!("Accepting commands on {:?}", path);
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let requests = requests.clone();
+                            std::thread::spawn(move || {
+                                if let Err(err) = serve(stream, &requests) {
+                                    debug!("Control client failed: {}", err);
+                                }
+                            });
+                        }
+                        Err(err) => warn!("Failed to accept control connection: {}", err),
+                    }
+                }
+            });
+            Ok(Self { path })
+        }
+    }
+
+    impl Drop for ControlSocket {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn serve(stream: UnixStream, requests: &Sender<Request>) -> RahmenResult<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = submit(&line, requests);
+            let mut encoded = serde_json::to_string(&reply).map_err(std::io::Error::other)?;
+            encoded.push('\n');
+            writer.write_all(encoded.as_bytes())?;
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,17 @@
 //! Dataflow operators to handle images are required for Rahmen
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::config::{Filter, KenBurns, Sharpen};
 use crate::font::FontRenderer;
+use crate::ken_burns::{Pan, fit};
+use crate::mat::Passepartout;
+use crate::schedule::Dimming;
 use crate::{Timer, Vector};
-use image::{DynamicImage, GenericImageView};
+use image::{DynamicImage, GenericImageView, RgbImage};
 use timely::dataflow::channels::pact::Pipeline;
 use timely::dataflow::operators::Operator;
 use timely::dataflow::{Scope, Stream};
@@ -16,6 +22,9 @@ pub type ImageStream<S> = Stream<S, Arc<DynamicImage>>;
 /// A keyed stream of offset and image
 pub type ImagePosStream<S> = Stream<S, (usize, Vector, Arc<DynamicImage>)>;
 
+/// A stream of images with an optional focal point, relative to the image size
+pub type FocusedImageStream<S> = Stream<S, (Arc<DynamicImage>, Option<(f32, f32)>)>;
+
 /// A configuration stream
 pub type ConfigurationStream<S> = Stream<S, Configuration>;
 
@@ -34,6 +43,42 @@ pub enum Configuration {
     Splash(Arc<DynamicImage>),
     /// Greeting message
     Greeting(String),
+    /// Update the time each image is shown
+    Delay(Duration),
+    /// Update the resampling filter to scale images
+    Filter(Filter),
+    /// Update the unsharp mask to apply after downscaling
+    Sharpen(Option<Sharpen>),
+    /// Dim the screen, or restore full brightness
+    Dim(Option<Dimming>),
+    /// Show the previous image
+    Previous,
+    /// Show a specific image
+    Goto(PathBuf),
+    /// Show or hide the status line
+    StatusLine(bool),
+    /// Show the info line of the image instead of the status line, or switch back
+    ShowInfo(bool),
+}
+
+/// Scale an image to fit into `width` and `height` and optionally sharpen the result
+pub fn scale_image(
+    image: &DynamicImage,
+    width: u32,
+    height: u32,
+    filter: Filter,
+    sharpen: Option<Sharpen>,
+) -> DynamicImage {
+    let resized = {
+        let _t = Timer::new(|e| debug!("Scaling ({:?}) {}ms", filter, e.as_millis()));
+        image.resize(width, height, filter.into())
+    };
+    if let Some(sharpen) = sharpen {
+        let _t = Timer::new(|e| debug!("Sharpening {}ms", e.as_millis()));
+        resized.unsharpen(sharpen.sigma, sharpen.threshold)
+    } else {
+        resized
+    }
 }
 
 /// Format text for the status line trait
@@ -60,6 +105,7 @@ impl<S: Scope> FormatText<S> for Stream<S, Vec<String>> {
         let mut current_font_size = None;
         let mut current_font_canvas_vstretch = None;
         let mut current_text = None;
+        let mut visible = true;
         let mut in_buffer1 = vec![];
         let mut in_buffer2 = vec![];
         self.binary_notify(
@@ -100,6 +146,7 @@ impl<S: Scope> FormatText<S> for Stream<S, Vec<String>> {
                                 Configuration::ScreenDimensions(width, height) => {
                                     current_screen_dimension = Some((width, height))
                                 }
+                                Configuration::StatusLine(show) => visible = show,
                                 _ => {}
                             }
                         }
@@ -120,13 +167,18 @@ impl<S: Scope> FormatText<S> for Stream<S, Vec<String>> {
                     ) {
                         // font canvas height, factor controls vertical padding
                         let canvas_height = font_size * font_canvas_vstretch;
-                        let img = font_renderer
-                            .render(
-                                text.iter().map(String::as_str),
-                                font_size,
-                                (dimension.0, canvas_height as _),
-                            )
-                            .unwrap();
+                        let img = if visible {
+                            font_renderer
+                                .render(
+                                    text.iter().map(String::as_str),
+                                    font_size,
+                                    (dimension.0, canvas_height as _),
+                                )
+                                .unwrap()
+                        } else {
+                            // keep the layout, but cover the text
+                            DynamicImage::ImageRgb8(RgbImage::new(dimension.0, canvas_height as _))
+                        };
                         out.session(&time).give((
                             key,
                             Vector::new(0, dimension.1 as i32 - canvas_height as i32),
@@ -139,6 +191,64 @@ impl<S: Scope> FormatText<S> for Stream<S, Vec<String>> {
     }
 }
 
+/// Choose between the status line and the info line of each image
+pub trait InfoText<S: Scope> {
+    /// Pass on the status line of each `(status line, info line)` pair, or its info line while
+    /// showing info. The current line is passed on again when switching.
+    fn info_text(&self, configuration_stream: &ConfigurationStream<S>) -> Stream<S, String>;
+}
+
+impl<S: Scope> InfoText<S> for Stream<S, (String, String)> {
+    fn info_text(&self, configuration_stream: &ConfigurationStream<S>) -> Stream<S, String> {
+        let mut buffer1 = vec![];
+        let mut buffer2 = vec![];
+        let mut text_stash = HashMap::new();
+        let mut configuration_stash = HashMap::new();
+        let mut current_text: Option<(String, String)> = None;
+        let mut show_info = false;
+        self.binary_notify(
+            configuration_stream,
+            Pipeline,
+            Pipeline,
+            "Info text",
+            None,
+            move |in1, in2, out, not| {
+                in1.for_each(|time, data| {
+                    data.swap(&mut buffer1);
+                    if let Some(text) = buffer1.drain(..).next_back() {
+                        text_stash.insert(time.time().clone(), text);
+                        not.notify_at(time.retain());
+                    }
+                });
+                in2.for_each(|time, data| {
+                    data.swap(&mut buffer2);
+                    for configuration in buffer2.drain(..) {
+                        if let Configuration::ShowInfo(show) = configuration {
+                            configuration_stash.insert(time.time().clone(), show);
+                        }
+                    }
+                    not.notify_at(time.retain());
+                });
+                not.for_each(|time, _cnt, _not| {
+                    let mut changed = false;
+                    if let Some(show) = configuration_stash.remove(time.time()) {
+                        changed = show != show_info;
+                        show_info = show;
+                    }
+                    if let Some(text) = text_stash.remove(time.time()) {
+                        current_text = Some(text);
+                        changed = true;
+                    }
+                    if let (true, Some((status, info))) = (changed, &current_text) {
+                        let text = if show_info { info } else { status };
+                        out.session(&time).give(text.clone());
+                    }
+                });
+            },
+        )
+    }
+}
+
 /// Resize an image to match its viewport size
 pub trait ResizeImage<S: Scope> {
     /// Resize an image
@@ -160,6 +270,8 @@ impl<S: Scope> ResizeImage<S> for ImageStream<S> {
         let mut img_stash = HashMap::new();
         let mut configuration_stash = HashMap::new();
         let mut current_screen_size = None;
+        let mut current_filter = Filter::Triangle;
+        let mut current_sharpen = None;
         let mut current_image = None;
         self.binary_notify(
             configuration_stream,
@@ -189,8 +301,13 @@ impl<S: Scope> ResizeImage<S> for ImageStream<S> {
                 not.for_each(|time, _cnt, _not| {
                     if let Some(configurations) = configuration_stash.remove(time.time()) {
                         for configuration in configurations {
-                            if let Configuration::ScreenDimensions(width, height) = configuration {
-                                current_screen_size = Some((width, height))
+                            match configuration {
+                                Configuration::ScreenDimensions(width, height) => {
+                                    current_screen_size = Some((width, height))
+                                }
+                                Configuration::Filter(filter) => current_filter = filter,
+                                Configuration::Sharpen(sharpen) => current_sharpen = sharpen,
+                                _ => {}
                             }
                         }
                     }
@@ -200,10 +317,12 @@ impl<S: Scope> ResizeImage<S> for ImageStream<S> {
                     if let (Some(screen_size), Some(image)) =
                         (current_screen_size, current_image.as_ref())
                     {
-                        let resized = image.resize(
+                        let resized = scale_image(
+                            image,
                             screen_size.0,
                             screen_size.1,
-                            image::imageops::FilterType::Triangle,
+                            current_filter,
+                            current_sharpen,
                         );
                         let x_offset = (screen_size.0 - resized.dimensions().0) / 2;
                         let y_offset = (screen_size.1 - resized.dimensions().1) / 2;
@@ -218,3 +337,340 @@ impl<S: Scope> ResizeImage<S> for ImageStream<S> {
         )
     }
 }
+
+/// Slowly pan and zoom across an image over its display time (Ken Burns effect)
+pub trait PanZoomImage<S: Scope> {
+    /// Pan and zoom across images, emitting a new view several times per second
+    fn pan_zoom_image(
+        &self,
+        configuration_stream: &ConfigurationStream<S>,
+        key: usize,
+        settings: KenBurns,
+    ) -> ImagePosStream<S>;
+}
+
+impl<S: Scope<Timestamp = Duration>> PanZoomImage<S> for FocusedImageStream<S> {
+    fn pan_zoom_image(
+        &self,
+        configuration_stream: &ConfigurationStream<S>,
+        key: usize,
+        settings: KenBurns,
+    ) -> ImagePosStream<S> {
+        let zoom = settings.zoom.unwrap_or(1.2).max(1.);
+        let interval = Duration::from_secs_f32(1. / settings.fps.unwrap_or(10.).max(0.1));
+        let mut buffer1 = vec![];
+        let mut buffer2 = vec![];
+        let mut img_stash = HashMap::new();
+        let mut configuration_stash = HashMap::new();
+        let mut current_screen_size = None;
+        let mut current_delay = None;
+        let mut current_filter = Filter::Triangle;
+        let mut current_sharpen = None;
+        let mut current_image: Option<(Arc<DynamicImage>, _)> = None;
+        // working image, visible size, offset, motion and start time of the current slide
+        let mut current_slide = None;
+        // time of the pending frame notification, if any
+        let mut next_frame = None;
+        self.binary_notify(
+            configuration_stream,
+            Pipeline,
+            Pipeline,
+            "Pan and zoom image",
+            None,
+            move |in1, in2, out, not| {
+                let _t = Timer::new(|e| debug!("Pan and zoom op {}ms", e.as_millis()));
+                in1.for_each(|time, data| {
+                    data.swap(&mut buffer1);
+                    for img in buffer1.drain(..) {
+                        img_stash.insert(*time.time(), img);
+                    }
+                    not.notify_at(time.retain());
+                });
+                in2.for_each(|time, data| {
+                    data.swap(&mut buffer2);
+                    for configuration in buffer2.drain(..) {
+                        configuration_stash
+                            .entry(*time.time())
+                            .or_insert_with(Vec::new)
+                            .push(configuration);
+                    }
+                    not.notify_at(time.retain());
+                });
+                not.for_each(|time, _cnt, not| {
+                    if next_frame == Some(*time.time()) {
+                        next_frame = None;
+                    }
+                    let mut changed = false;
+                    if let Some(configurations) = configuration_stash.remove(time.time()) {
+                        for configuration in configurations {
+                            match configuration {
+                                Configuration::ScreenDimensions(width, height) => {
+                                    current_screen_size = Some((width, height));
+                                    changed = true;
+                                }
+                                Configuration::Delay(delay) => current_delay = Some(delay),
+                                Configuration::Filter(filter) => {
+                                    current_filter = filter;
+                                    changed = true;
+                                }
+                                Configuration::Sharpen(sharpen) => {
+                                    current_sharpen = sharpen;
+                                    changed = true;
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    if let Some(img) = img_stash.remove(time.time()) {
+                        current_image = Some(img);
+                        changed = true;
+                    }
+                    if changed {
+                        current_slide = if let (Some(screen_size), Some((image, focal_point))) =
+                            (current_screen_size, current_image.as_ref())
+                        {
+                            let visible = fit(image.dimensions(), screen_size);
+                            let working = scale_image(
+                                image,
+                                (visible.0 as f32 * zoom) as u32,
+                                (visible.1 as f32 * zoom) as u32,
+                                current_filter,
+                                current_sharpen,
+                            );
+                            let offset = Vector::new(
+                                (screen_size.0 - visible.0) as i32 / 2,
+                                (screen_size.1 - visible.1) as i32 / 2,
+                            );
+                            Some((
+                                working,
+                                visible,
+                                offset,
+                                Pan::choose(*focal_point, zoom),
+                                *time.time(),
+                            ))
+                        } else {
+                            None
+                        };
+                    }
+                    if let Some((working, visible, offset, pan, start)) = &current_slide {
+                        let elapsed = time.time().saturating_sub(*start).as_secs_f32();
+                        let progress = current_delay
+                            .map_or(1., |delay: Duration| elapsed / delay.as_secs_f32())
+                            .min(1.);
+                        let (x, y, width, height) =
+                            pan.view_at(progress).to_rect(working.dimensions());
+                        // resize the view directly, without copying it first
+                        let frame = match working {
+                            DynamicImage::ImageRgb8(rgb) => {
+                                DynamicImage::ImageRgb8(image::imageops::resize(
+                                    &*rgb.view(x, y, width, height),
+                                    visible.0,
+                                    visible.1,
+                                    current_filter.into(),
+                                ))
+                            }
+                            working => working.crop_imm(x, y, width, height).resize_exact(
+                                visible.0,
+                                visible.1,
+                                current_filter.into(),
+                            ),
+                        };
+                        out.session(&time).give((key, *offset, Arc::new(frame)));
+                        if progress < 1. && next_frame.is_none() && !not.frontier(0).is_empty() {
+                            let mut next_time = *time.time() + interval;
+                            while !not.frontier(0).less_equal(&next_time) {
+                                next_time += interval;
+                            }
+                            not.notify_at(time.delayed(&next_time));
+                            next_frame = Some(next_time);
+                        }
+                    }
+                })
+            },
+        )
+    }
+}
+
+/// Place images on a mat, with an optional border and drop shadow
+pub trait MatImage<S: Scope> {
+    /// Compose images onto a mat filling the screen dimensions. The images' anchors are
+    /// expected relative to the screen dimensions without the passepartout's inset. The mat is
+    /// drawn under `mat_key` only when the size or position of an image changes, otherwise just
+    /// the image is placed on the mat on screen.
+    fn mat_image(
+        &self,
+        configuration_stream: &ConfigurationStream<S>,
+        passepartout: Passepartout,
+        mat_key: usize,
+    ) -> ImagePosStream<S>;
+}
+
+impl<S: Scope> MatImage<S> for ImagePosStream<S> {
+    fn mat_image(
+        &self,
+        configuration_stream: &ConfigurationStream<S>,
+        passepartout: Passepartout,
+        mat_key: usize,
+    ) -> ImagePosStream<S> {
+        let mut buffer1 = vec![];
+        let mut buffer2 = vec![];
+        let mut img_stash = HashMap::new();
+        let mut configuration_stash = HashMap::new();
+        let mut current_screen_size = None;
+        // the latest image of each key, kept until the screen dimensions are known
+        let mut pending = HashMap::new();
+        // the screen size, anchor and image size the mat was drawn for, for each key
+        let mut layouts = HashMap::new();
+        let inset = passepartout.inset() as i32;
+        self.binary_notify(
+            configuration_stream,
+            Pipeline,
+            Pipeline,
+            "Mat image",
+            None,
+            move |in1, in2, out, not| {
+                let _t = Timer::new(|e| debug!("Mat image op {}ms", e.as_millis()));
+                in1.for_each(|time, data| {
+                    data.swap(&mut buffer1);
+                    img_stash
+                        .entry(time.time().clone())
+                        .or_insert_with(Vec::new)
+                        .append(&mut buffer1);
+                    not.notify_at(time.retain());
+                });
+                in2.for_each(|time, data| {
+                    data.swap(&mut buffer2);
+                    for configuration in buffer2.drain(..) {
+                        configuration_stash
+                            .entry(time.time().clone())
+                            .or_insert_with(Vec::new)
+                            .push(configuration);
+                    }
+                    not.notify_at(time.retain());
+                });
+                not.for_each(|time, _cnt, _not| {
+                    if let Some(configurations) = configuration_stash.remove(time.time()) {
+                        for configuration in configurations {
+                            if let Configuration::ScreenDimensions(width, height) = configuration {
+                                current_screen_size = Some((width, height))
+                            }
+                        }
+                    }
+                    for (key, anchor, img) in img_stash.remove(time.time()).unwrap_or_default() {
+                        pending.insert(key, (anchor, img));
+                    }
+                    let Some(screen_size) = current_screen_size else {
+                        return;
+                    };
+                    let mut session = out.session(&time);
+                    for (key, (anchor, img)) in pending.drain() {
+                        session.give((key, anchor + Vector::new(inset, inset), Arc::clone(&img)));
+                        let layout = (screen_size, anchor, img.dimensions());
+                        if layouts.insert(key, layout) != Some(layout) {
+                            // the mat covers the image placed before, so it includes the image
+                            let composed = passepartout.compose(screen_size, anchor, &img);
+                            session.give((mat_key, Vector::new(0, 0), Arc::new(composed)));
+                        }
+                    }
+                })
+            },
+        )
+    }
+}
+
+/// Dim images according to the current dimming configuration
+pub trait DimImage<S: Scope> {
+    /// Dim images. In clock-only mode, all images except the one with `status_key` are replaced by
+    /// black images of the same size. When the dimming changes, the last image of each key is
+    /// shown again with the new dimming.
+    fn dim_image(
+        &self,
+        configuration_stream: &ConfigurationStream<S>,
+        status_key: usize,
+    ) -> ImagePosStream<S>;
+}
+
+impl<S: Scope> DimImage<S> for ImagePosStream<S> {
+    fn dim_image(
+        &self,
+        configuration_stream: &ConfigurationStream<S>,
+        status_key: usize,
+    ) -> ImagePosStream<S> {
+        let mut buffer1 = vec![];
+        let mut buffer2 = vec![];
+        let mut img_stash = HashMap::new();
+        let mut configuration_stash = HashMap::new();
+        let mut current_dimming: Option<Dimming> = None;
+        // the last undimmed image of each key
+        let mut last = HashMap::new();
+        self.binary_notify(
+            configuration_stream,
+            Pipeline,
+            Pipeline,
+            "Dim image",
+            None,
+            move |in1, in2, out, not| {
+                let _t = Timer::new(|e| debug!("Dim image op {}ms", e.as_millis()));
+                in1.for_each(|time, data| {
+                    data.swap(&mut buffer1);
+                    img_stash
+                        .entry(time.time().clone())
+                        .or_insert_with(Vec::new)
+                        .append(&mut buffer1);
+                    not.notify_at(time.retain());
+                });
+                in2.for_each(|time, data| {
+                    data.swap(&mut buffer2);
+                    for configuration in buffer2.drain(..) {
+                        configuration_stash
+                            .entry(time.time().clone())
+                            .or_insert_with(Vec::new)
+                            .push(configuration);
+                    }
+                    not.notify_at(time.retain());
+                });
+                not.for_each(|time, _cnt, _not| {
+                    let mut changed = false;
+                    if let Some(configurations) = configuration_stash.remove(time.time()) {
+                        for configuration in configurations {
+                            if let Configuration::Dim(dimming) = configuration {
+                                changed |= dimming != current_dimming;
+                                current_dimming = dimming;
+                            }
+                        }
+                    }
+                    let images = img_stash.remove(time.time()).unwrap_or_default();
+                    for (key, anchor, img) in &images {
+                        last.insert(*key, (*anchor, Arc::clone(img)));
+                    }
+                    // images on screen which would otherwise keep the previous dimming, in the
+                    // order of their keys to draw images over the mat again
+                    let mut redimmed: Vec<_> = if changed {
+                        last.iter()
+                            .filter(|(key, _)| !images.iter().any(|(k, _, _)| k == *key))
+                            .map(|(key, (anchor, img))| (*key, *anchor, Arc::clone(img)))
+                            .collect()
+                    } else {
+                        vec![]
+                    };
+                    redimmed.sort_by_key(|(key, _, _)| *key);
+                    if images.is_empty() && redimmed.is_empty() {
+                        return;
+                    }
+                    let dimming = current_dimming;
+                    out.session(&time)
+                        .give_iterator(redimmed.into_iter().chain(images).map(
+                            |(key, anchor, img)| match dimming {
+                                Some(dimming) if dimming.clock_only && key != status_key => {
+                                    let black = RgbImage::new(img.width(), img.height());
+                                    (key, anchor, Arc::new(DynamicImage::ImageRgb8(black)))
+                                }
+                                Some(dimming) => (key, anchor, Arc::new(dimming.apply(&img))),
+                                None => (key, anchor, img),
+                            },
+                        ));
+                })
+            },
+        )
+    }
+}
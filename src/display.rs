@@ -1,10 +1,67 @@
 //! Functionality to render images on a display
 
-use image::DynamicImage;
+use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
 
 use crate::Vector;
 use crate::errors::RahmenResult;
 
+/// Color of blanked areas
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// The frame of a display in memory, drawn into by [`Display::render`] and [`Display::blank`]
+/// and copied to the screen on [`Display::update`]
+#[derive(Debug, Default, Clone)]
+pub struct ShadowBuffer {
+    image: RgbaImage,
+}
+
+impl ShadowBuffer {
+    /// Create a black frame with `dimensions`
+    pub fn new(dimensions: (u32, u32)) -> Self {
+        Self {
+            image: RgbaImage::from_pixel(dimensions.0, dimensions.1, BLACK),
+        }
+    }
+
+    /// Replace the frame with a black one if its dimensions differ. Returns true if it was
+    /// replaced.
+    pub fn match_dimensions(&mut self, dimensions: (u32, u32)) -> bool {
+        if self.image.dimensions() == dimensions {
+            return false;
+        }
+        *self = Self::new(dimensions);
+        true
+    }
+
+    /// Copy `img` into the frame at `anchor`
+    pub fn render(&mut self, anchor: Vector, img: &RgbaImage) -> RahmenResult<()> {
+        self.image
+            .copy_from(img, anchor.x() as _, anchor.y() as _)?;
+        Ok(())
+    }
+
+    /// Fill the rectangle at `anchor` with `size` with black
+    pub fn blank(&mut self, anchor: Vector, size: Vector) -> RahmenResult<()> {
+        let black = image::FlatSamples::with_monocolor(&BLACK, size.x() as _, size.y() as _);
+        self.image.copy_from(
+            &black.as_view::<Rgba<u8>>().unwrap(),
+            anchor.x() as _,
+            anchor.y() as _,
+        )?;
+        Ok(())
+    }
+
+    /// The frame
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// The dimensions of the frame
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+}
+
 /// Trait describing the interface to display an image
 pub trait Display {
     /// Reveal an image to the user
@@ -19,4 +76,21 @@ pub trait Display {
 
     /// Return the dimensions of the display as `(width, height)`
     fn dimensions(&self) -> (u32, u32);
+
+    /// Put the display into power-save mode. The default implementation does nothing.
+    fn power_off(&mut self) -> RahmenResult<()> {
+        Ok(())
+    }
+
+    /// Wake the display from power-save mode and show the last frame again. The default
+    /// implementation does nothing.
+    fn power_on(&mut self) -> RahmenResult<()> {
+        Ok(())
+    }
+
+    /// Return the names of the keys pressed since the last call, e.g. `KEY_RIGHT`. The default
+    /// implementation reports no keys.
+    fn pressed_keys(&mut self) -> Vec<String> {
+        Vec::new()
+    }
 }
@@ -0,0 +1,258 @@
+//! Functionality to render images through Linux DRM/KMS.
+//!
+//! Unlike the framebuffer backend, this drives `/dev/dri/card*` through the kernel mode-setting
+//! interface, which is available even where the legacy fbdev interface is disabled. Two dumb
+//! buffers are drawn alternately, and page flips swap them at vertical blank for tear-free
+//! updates. Without a GPU, it can be tried out with the `vkms` virtual KMS driver.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind};
+use std::os::unix::io::{AsFd, BorrowedFd};
+use std::path::Path;
+use std::time::Duration;
+
+use drm::buffer::DrmFourcc;
+use drm::control::dumbbuffer::DumbBuffer;
+use drm::control::{
+    Device as ControlDevice, Event, Mode, ModeTypeFlags, PageFlipFlags, connector, crtc,
+};
+use image::DynamicImage;
+
+use crate::Vector;
+use crate::config::Rotation;
+use crate::display::{Display, ShadowBuffer};
+use crate::display_framebuffer::PixelFormat;
+use crate::errors::{RahmenError, RahmenResult};
+
+/// An open DRM device node
+#[derive(Debug)]
+struct Card(File);
+
+impl AsFd for Card {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl drm::Device for Card {}
+impl ControlDevice for Card {}
+
+/// A dumb buffer registered as a framebuffer
+#[derive(Debug)]
+struct ScanoutBuffer {
+    buffer: DumbBuffer,
+    framebuffer: drm::control::framebuffer::Handle,
+}
+
+/// A display driver for DRM/KMS devices.
+///
+/// Like [`crate::display_framebuffer::FramebufferDisplay`], the backing buffer is an
+/// [`RgbaImage`] with bytes in the [`PixelFormat::XRGB8888`] layout of the scanout buffers.
+#[derive(Debug)]
+pub struct DrmDisplay {
+    card: Card,
+    connector: connector::Handle,
+    crtc: crtc::Handle,
+    mode: Mode,
+    saved_crtc: crtc::Info,
+    buffers: [ScanoutBuffer; 2],
+    /// Index of the buffer currently scanned out
+    front: usize,
+    flip_pending: bool,
+    image: ShadowBuffer,
+    rotation: Rotation,
+    powered: bool,
+}
+
+fn not_found(message: &str) -> RahmenError {
+    RahmenError::IoError(Error::new(ErrorKind::NotFound, message))
+}
+
+impl DrmDisplay {
+    /// Open the DRM device at `path` and set the preferred mode of the first connected output,
+    /// rotating the content clockwise by `rotation`
+    pub fn new<P: AsRef<Path>>(path: P, rotation: Rotation) -> RahmenResult<Self> {
+        let card = Card(OpenOptions::new().read(true).write(true).open(path)?);
+        let resources = card.resource_handles()?;
+        let connector = resources
+            .connectors()
+            .iter()
+            .flat_map(|handle| card.get_connector(*handle, true))
+            .find(|info| info.state() == connector::State::Connected)
+            .ok_or_else(|| not_found("No connected DRM connector"))?;
+        let mode = *connector
+            .modes()
+            .iter()
+            .find(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
+            .or_else(|| connector.modes().first())
+            .ok_or_else(|| not_found("No modes found on DRM connector"))?;
+        // Prefer the CRTC already driving the connector, then any compatible one
+        let crtc = connector
+            .current_encoder()
+            .and_then(|encoder| card.get_encoder(encoder).ok())
+            .and_then(|encoder| encoder.crtc())
+            .or_else(|| {
+                connector
+                    .encoders()
+                    .iter()
+                    .flat_map(|encoder| card.get_encoder(*encoder))
+                    .flat_map(|encoder| resources.filter_crtcs(encoder.possible_crtcs()))
+                    .next()
+            })
+            .ok_or_else(|| not_found("No CRTC found for DRM connector"))?;
+        let saved_crtc = card.get_crtc(crtc)?;
+
+        let (width, height) = mode.size();
+        let create_buffer = || -> RahmenResult<ScanoutBuffer> {
+            let mut buffer =
+                card.create_dumb_buffer((width.into(), height.into()), DrmFourcc::Xrgb8888, 32)?;
+            card.map_dumb_buffer(&mut buffer)?.fill(0);
+            let framebuffer = card.add_framebuffer(&buffer, 24, 32)?;
+            Ok(ScanoutBuffer {
+                buffer,
+                framebuffer,
+            })
+        };
+        let buffers = [create_buffer()?, create_buffer()?];
+        card.set_crtc(
+            crtc,
+            Some(buffers[0].framebuffer),
+            (0, 0),
+            &[connector.handle()],
+            Some(mode),
+        )?;
+        info!("Using DRM mode {:?} on {:?}", mode, connector.handle());
+
+        Ok(Self {
+            card,
+            connector: connector.handle(),
+            crtc,
+            mode,
+            saved_crtc,
+            buffers,
+            front: 0,
+            flip_pending: false,
+            image: Default::default(),
+            rotation,
+            powered: true,
+        })
+    }
+
+    /// Enter the control loop. This will periodically trigger the callback, until it returns an
+    /// `Err` result.
+    pub fn main_loop<F: FnMut(&mut dyn Display) -> RahmenResult<()>>(&mut self, mut callback: F) {
+        while callback(self).is_ok() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// Block until the pending page flip has completed, so its buffer can be reused
+    fn wait_for_flip(&mut self) -> RahmenResult<()> {
+        while self.flip_pending {
+            for event in self.card.receive_events()? {
+                if let Event::PageFlip(flip) = event {
+                    if flip.crtc == self.crtc {
+                        self.flip_pending = false;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for DrmDisplay {
+    fn render(&mut self, _key: usize, anchor: Vector, img: &DynamicImage) -> RahmenResult<()> {
+        let _t = crate::Timer::new(|e| debug!("Rendering {}ms", e.as_millis()));
+        self.image.match_dimensions(self.dimensions());
+        self.image
+            .render(anchor, &PixelFormat::XRGB8888.arrange(img))
+    }
+
+    fn blank(&mut self, _key: usize, anchor: Vector, size: Vector) -> RahmenResult<()> {
+        let _t = crate::Timer::new(|e| debug!("Blanking {}ms", e.as_millis()));
+        self.image.match_dimensions(self.dimensions());
+        self.image.blank(anchor, size)
+    }
+
+    fn update(&mut self) -> RahmenResult<()> {
+        if !self.powered {
+            return Ok(());
+        }
+        let _t = crate::Timer::new(|e| debug!("Updating {}ms", e.as_millis()));
+        self.image.match_dimensions(self.dimensions());
+        self.wait_for_flip()?;
+        let back = 1 - self.front;
+        let buffer = &mut self.buffers[back].buffer;
+        let stride = drm::buffer::Buffer::pitch(buffer) as usize;
+        let mut mapping = self.card.map_dumb_buffer(buffer)?;
+        let frame = mapping.as_mut();
+        let image = self.image.image();
+        let (width, height) = image.dimensions();
+        PixelFormat::XRGB8888.copy_damage(
+            image,
+            &[(0, 0, width, height)],
+            self.rotation,
+            false,
+            frame,
+            stride,
+        );
+        drop(mapping);
+        self.card.page_flip(
+            self.crtc,
+            self.buffers[back].framebuffer,
+            PageFlipFlags::EVENT,
+            None,
+        )?;
+        self.flip_pending = true;
+        self.front = back;
+        Ok(())
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        let (width, height) = self.mode.size();
+        if self.rotation.is_transposed() {
+            (height.into(), width.into())
+        } else {
+            (width.into(), height.into())
+        }
+    }
+
+    fn power_off(&mut self) -> RahmenResult<()> {
+        self.wait_for_flip()?;
+        self.powered = false;
+        // Disabling the CRTC turns off the output
+        self.card.set_crtc(self.crtc, None, (0, 0), &[], None)?;
+        Ok(())
+    }
+
+    fn power_on(&mut self) -> RahmenResult<()> {
+        self.card.set_crtc(
+            self.crtc,
+            Some(self.buffers[self.front].framebuffer),
+            (0, 0),
+            &[self.connector],
+            Some(self.mode),
+        )?;
+        self.powered = true;
+        self.update()
+    }
+}
+
+impl Drop for DrmDisplay {
+    fn drop(&mut self) {
+        let _ = self.wait_for_flip();
+        // Restore the previous configuration, e.g. the console
+        let _ = self.card.set_crtc(
+            self.saved_crtc.handle(),
+            self.saved_crtc.framebuffer(),
+            self.saved_crtc.position(),
+            &[self.connector],
+            self.saved_crtc.mode(),
+        );
+        for buffer in &self.buffers {
+            let _ = self.card.destroy_framebuffer(buffer.framebuffer);
+            let _ = self.card.destroy_dumb_buffer(buffer.buffer);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Functionality to render images into PNG files, without any display hardware.
+//!
+//! This is useful to run Rahmen in CI or in containers, for golden-image tests, and to publish
+//! what is currently shown, e.g. on a web page.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use image::{DynamicImage, RgbaImage};
+
+use crate::Vector;
+use crate::display::{Display, ShadowBuffer};
+use crate::errors::RahmenResult;
+
+/// Name of the file holding the latest frame
+pub const CURRENT_FILE_NAME: &str = "current.png";
+
+/// A display driver writing each update as a PNG file to a directory.
+///
+/// The backing buffer is an [`RgbaImage`] with a fixed, virtual resolution. By default, every
+/// update replaces `current.png` in the directory. The file is written under a temporary name and
+/// then renamed, so readers never see a partially written image. With `sequence`, every update is
+/// written to a new numbered file instead, e.g. `frame-000001.png`.
+#[derive(Debug)]
+pub struct FileDisplay {
+    directory: PathBuf,
+    image: ShadowBuffer,
+    sequence: bool,
+    frame: u64,
+}
+
+impl FileDisplay {
+    /// Create a new file display writing to `directory`, which is created if it does not exist
+    pub fn new<P: AsRef<Path>>(
+        directory: P,
+        dimensions: (u32, u32),
+        sequence: bool,
+    ) -> RahmenResult<Self> {
+        std::fs::create_dir_all(directory.as_ref())?;
+        Ok(Self {
+            directory: directory.as_ref().to_path_buf(),
+            image: ShadowBuffer::new(dimensions),
+            sequence,
+            frame: 0,
+        })
+    }
+
+    /// Enter the control loop. This will periodically trigger the callback, until it returns an
+    /// `Err` result.
+    pub fn main_loop<F: FnMut(&mut dyn Display) -> RahmenResult<()>>(&mut self, mut callback: F) {
+        while callback(self).is_ok() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// The image as shown after the last render or blank operation
+    pub fn image(&self) -> &RgbaImage {
+        self.image.image()
+    }
+}
+
+impl Display for FileDisplay {
+    fn render(&mut self, _key: usize, anchor: Vector, img: &DynamicImage) -> RahmenResult<()> {
+        self.image.render(anchor, &img.to_rgba8())
+    }
+
+    fn blank(&mut self, _key: usize, anchor: Vector, size: Vector) -> RahmenResult<()> {
+        self.image.blank(anchor, size)
+    }
+
+    fn update(&mut self) -> RahmenResult<()> {
+        let _t = crate::Timer::new(|e| debug!("Writing frame {}ms", e.as_millis()));
+        self.frame += 1;
+        if self.sequence {
+            let path = self.directory.join(format!("frame-{:06}.png", self.frame));
+            self.image.image().save(path)?;
+        } else {
+            let temporary = self.directory.join(".current.png.tmp");
+            self.image
+                .image()
+                .save_with_format(&temporary, image::ImageFormat::Png)?;
+            std::fs::rename(temporary, self.directory.join(CURRENT_FILE_NAME))?;
+        }
+        Ok(())
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+}
@@ -1,32 +1,300 @@
 //! Functionality to render images on a Linux framebuffer
 
-use crate::display::Display;
+use crate::config::Rotation;
+use crate::display::{Display, ShadowBuffer};
 use crate::errors::{RahmenError, RahmenResult};
+use crate::power::SysfsPower;
 
 use crate::Vector;
-use framebuffer::Framebuffer;
-use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
+use framebuffer::{Framebuffer, VarScreeninfo};
+use image::{DynamicImage, Rgba, RgbaImage};
 use std::time::Duration;
 
+/// Number of dirty rectangles to track before merging them into their bounding box
+pub const MAX_DAMAGE: usize = 16;
+
+/// 4x4 Bayer matrix for ordered dithering, with thresholds from 0 to 15
+const BAYER: [[u16; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Position of a color channel within a packed pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channel {
+    /// Offset of the least significant bit
+    pub offset: u32,
+    /// Number of bits
+    pub length: u32,
+}
+
+/// Packed pixel layout of a framebuffer, such as RGB565, RGB888 or XRGB8888
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    /// Number of bytes per pixel
+    pub bytes_per_pixel: usize,
+    /// Red channel
+    pub red: Channel,
+    /// Green channel
+    pub green: Channel,
+    /// Blue channel
+    pub blue: Channel,
+}
+
+impl PixelFormat {
+    /// 32-bit pixels with 8 bits per channel in the order blue, green, red and an unused byte
+    /// in memory, the common layout of framebuffers and DRM scanout buffers
+    pub const XRGB8888: Self = Self {
+        bytes_per_pixel: 4,
+        red: Channel {
+            offset: 16,
+            length: 8,
+        },
+        green: Channel {
+            offset: 8,
+            length: 8,
+        },
+        blue: Channel {
+            offset: 0,
+            length: 8,
+        },
+    };
+
+    /// Read the pixel layout from the framebuffer's variable screen information
+    pub fn from_screen_info(info: &VarScreeninfo) -> RahmenResult<Self> {
+        if !matches!(info.bits_per_pixel, 16 | 24 | 32) {
+            return Err(RahmenError::UnsupportedPixelFormat(format!(
+                "{} bits per pixel",
+                info.bits_per_pixel
+            )));
+        }
+        let channel = |bitfield: &framebuffer::Bitfield| Channel {
+            offset: bitfield.offset,
+            length: bitfield.length,
+        };
+        Ok(Self {
+            bytes_per_pixel: info.bits_per_pixel as usize / 8,
+            red: channel(&info.red),
+            green: channel(&info.green),
+            blue: channel(&info.blue),
+        })
+    }
+
+    /// Pack an RGB pixel at frame position `(x, y)`. With `dither`, channels with less than 8
+    /// bits are dithered using an ordered 4x4 pattern.
+    pub fn encode(&self, rgb: [u8; 3], x: u32, y: u32, dither: bool) -> u32 {
+        let threshold = BAYER[y as usize % 4][x as usize % 4];
+        let pack = |value: u8, channel: Channel| {
+            let length = channel.length.min(8);
+            if length == 0 {
+                return 0;
+            }
+            let dropped = 8 - length;
+            let value = if dither && dropped > 0 {
+                // spread the threshold across one quantization step
+                (value as u16 + ((threshold << dropped) >> 4)).min(255) as u8
+            } else {
+                value
+            };
+            ((value >> dropped) as u32) << channel.offset
+        };
+        pack(rgb[0], self.red) | pack(rgb[1], self.green) | pack(rgb[2], self.blue)
+    }
+
+    /// The byte positions of the red, green and blue channels within a pixel, if the pixels are
+    /// 32 bits wide with one byte per channel and the last byte unused, like XRGB8888. Such pixels
+    /// are copied without packing.
+    pub fn byte_order(&self) -> Option<[usize; 3]> {
+        let byte = |channel: Channel| {
+            (channel.length == 8 && channel.offset % 8 == 0 && channel.offset < 24)
+                .then_some(channel.offset as usize / 8)
+        };
+        let order = [byte(self.red)?, byte(self.green)?, byte(self.blue)?];
+        let distinct = order[0] != order[1] && order[1] != order[2] && order[0] != order[2];
+        (self.bytes_per_pixel == 4 && distinct).then_some(order)
+    }
+
+    /// Store a packed pixel at the start of `target` in little-endian byte order
+    pub fn write(&self, value: u32, target: &mut [u8]) {
+        target[..self.bytes_per_pixel]
+            .copy_from_slice(&value.to_le_bytes()[..self.bytes_per_pixel]);
+    }
+
+    /// Convert an image to the layout of the backing buffer: with a [`Self::byte_order`], the
+    /// channels are moved to their bytes, otherwise the image stays RGBA.
+    pub fn arrange(&self, img: &DynamicImage) -> RgbaImage {
+        let mut rgba = img.to_rgba8();
+        if let Some([red, green, blue]) = self.byte_order() {
+            for pixel in rgba.pixels_mut() {
+                let Rgba([r, g, b, _]) = *pixel;
+                pixel.0 = [0; 4];
+                pixel.0[red] = r;
+                pixel.0[green] = g;
+                pixel.0[blue] = b;
+            }
+        }
+        rgba
+    }
+
+    /// Copy the `damage` rectangles of a backing buffer `image` arranged by [`Self::arrange`]
+    /// into `frame`, with rows `stride` bytes apart. The rectangles are rotated clockwise by
+    /// `rotation`, and packed into this format unless the buffer is in its byte order already.
+    pub fn copy_damage(
+        &self,
+        image: &RgbaImage,
+        damage: &[Rect],
+        rotation: Rotation,
+        dither: bool,
+        frame: &mut [u8],
+        stride: usize,
+    ) {
+        let dimensions = image.dimensions();
+        match (self.byte_order(), rotation) {
+            // the buffer is in the framebuffer's pixel format already, copy the rows of each rect
+            (Some(_), Rotation::None) => {
+                let row_length = dimensions.0 as usize * 4;
+                let raw = image.as_raw();
+                for &(left, top, width, height) in damage {
+                    let (start, length) = (left as usize * 4, width as usize * 4);
+                    for y in top as usize..(top + height) as usize {
+                        let line = &raw[y * row_length + start..y * row_length + start + length];
+                        frame[y * stride + start..y * stride + start + length]
+                            .copy_from_slice(line);
+                    }
+                }
+            }
+            // rotate, but copy the pixels as they are
+            (Some(_), rotation) => {
+                for &(left, top, width, height) in damage {
+                    for y in top..top + height {
+                        for x in left..left + width {
+                            let (frame_x, frame_y) = rotation.map((x, y), dimensions);
+                            let offset = frame_y as usize * stride + frame_x as usize * 4;
+                            frame[offset..offset + 4].copy_from_slice(&image.get_pixel(x, y).0);
+                        }
+                    }
+                }
+            }
+            (None, rotation) => {
+                for &(left, top, width, height) in damage {
+                    for y in top..top + height {
+                        for x in left..left + width {
+                            let pixel = image.get_pixel(x, y);
+                            let (frame_x, frame_y) = rotation.map((x, y), dimensions);
+                            let value = self.encode(
+                                [pixel[0], pixel[1], pixel[2]],
+                                frame_x,
+                                frame_y,
+                                dither,
+                            );
+                            let offset =
+                                frame_y as usize * stride + frame_x as usize * self.bytes_per_pixel;
+                            self.write(value, &mut frame[offset..]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A rectangle `(x, y, width, height)`
+pub type Rect = (u32, u32, u32, u32);
+
+/// The rectangles of an image changed since the last update
+#[derive(Debug, Default)]
+pub struct Damage {
+    rects: Vec<Rect>,
+}
+
+impl Damage {
+    /// Mark the whole image with `dimensions` as changed
+    pub fn add_all(&mut self, (width, height): (u32, u32)) {
+        self.rects = vec![(0, 0, width, height)];
+    }
+
+    /// Mark the rectangle at `anchor` with `size` as changed, clipped to an image with
+    /// `dimensions`. Rectangles within a changed one are skipped, and more than [`MAX_DAMAGE`]
+    /// rectangles are merged into their bounding box.
+    pub fn add(&mut self, anchor: Vector, size: (u32, u32), (width, height): (u32, u32)) {
+        let left = anchor.x().clamp(0, width as i32) as u32;
+        let top = anchor.y().clamp(0, height as i32) as u32;
+        let right = (anchor.x() as i64 + size.0 as i64).clamp(0, width as i64) as u32;
+        let bottom = (anchor.y() as i64 + size.1 as i64).clamp(0, height as i64) as u32;
+        if right <= left || bottom <= top {
+            return;
+        }
+        let rect = (left, top, right - left, bottom - top);
+        let contains = |outer: &Rect| {
+            outer.0 <= rect.0
+                && outer.1 <= rect.1
+                && outer.0 + outer.2 >= rect.0 + rect.2
+                && outer.1 + outer.3 >= rect.1 + rect.3
+        };
+        if self.rects.iter().any(contains) {
+            return;
+        }
+        self.rects.push(rect);
+        if self.rects.len() > MAX_DAMAGE {
+            let left = self.rects.iter().map(|r| r.0).min().unwrap_or(0);
+            let top = self.rects.iter().map(|r| r.1).min().unwrap_or(0);
+            let right = self.rects.iter().map(|r| r.0 + r.2).max().unwrap_or(0);
+            let bottom = self.rects.iter().map(|r| r.1 + r.3).max().unwrap_or(0);
+            self.rects = vec![(left, top, right - left, bottom - top)];
+        }
+    }
+
+    /// The changed rectangles
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    /// Return the changed rectangles and mark the image as unchanged
+    pub fn take(&mut self) -> Vec<Rect> {
+        std::mem::take(&mut self.rects)
+    }
+}
+
 /// A display driver for Linux framebuffers.
 ///
-/// The backing buffer is an [`RgbaImage`] whose bytes are kept in the framebuffer's native
-/// BGRA channel order (the dedicated `Bgra` pixel type was removed from the `image` crate).
+/// The backing buffer is an [`RgbaImage`] with the logical, rotated dimensions. When copying it
+/// into the framebuffer, it is rotated and converted to the framebuffer's pixel format. Only the
+/// rectangles changed by [`Display::render`] and [`Display::blank`] since the last update are
+/// copied. For 32-bit pixel formats, the buffer holds the channels in the framebuffer's byte
+/// order, so it is copied without conversion.
 #[derive(Debug)]
 pub struct FramebufferDisplay {
     framebuffer: Framebuffer,
-    image: RgbaImage,
+    image: ShadowBuffer,
+    format: PixelFormat,
+    dither: bool,
+    rotation: Rotation,
+    power: Option<SysfsPower>,
+    powered: bool,
+    /// Rectangles of the image changed since the last update
+    damage: Damage,
 }
 
 impl FramebufferDisplay {
-    /// Crate a new framebuffer
-    pub fn new(mut framebuffer: Framebuffer) -> Self {
-        assert_eq!(framebuffer.var_screen_info.bits_per_pixel, 32);
+    /// Crate a new framebuffer, rotating its content clockwise by `rotation`. With `dither`,
+    /// colors are dithered on framebuffers with less than 8 bits per channel. If given, `power`
+    /// is used to switch the screen off and on.
+    pub fn new(
+        mut framebuffer: Framebuffer,
+        rotation: Rotation,
+        dither: bool,
+        power: Option<SysfsPower>,
+    ) -> RahmenResult<Self> {
+        let format = PixelFormat::from_screen_info(&framebuffer.var_screen_info)?;
+        info!("Framebuffer pixel format: {:?}", format);
         framebuffer.frame.fill(0);
-        Self {
+        Ok(Self {
             framebuffer,
             image: Default::default(),
-        }
+            format,
+            dither,
+            rotation,
+            power,
+            powered: true,
+            damage: Damage::default(),
+        })
     }
 
     /// Enter the control loop. This will periodically trigger the callback, until it returns an
@@ -37,57 +305,83 @@ impl FramebufferDisplay {
         }
     }
 
-    fn match_dimensions(&mut self) -> RahmenResult<()> {
-        if self.image.dimensions() != self.dimensions() {
-            self.image = RgbaImage::from_raw(
-                self.dimensions().0,
-                self.dimensions().1,
-                vec![0u8; (self.dimensions().0 * self.dimensions().1 * 4) as usize],
-            )
-            .ok_or(RahmenError::Terminate)?;
+    fn match_dimensions(&mut self) {
+        if self.image.match_dimensions(self.dimensions()) {
+            self.damage.add_all(self.image.dimensions());
         }
-        Ok(())
     }
 }
 
 impl Display for FramebufferDisplay {
     fn render(&mut self, _key: usize, anchor: Vector, img: &DynamicImage) -> RahmenResult<()> {
-        self.match_dimensions()?;
-        // Convert to RGBA and swap the red/blue channels to obtain the framebuffer's BGRA order.
-        let mut bgra = img.to_rgba8();
-        for pixel in bgra.pixels_mut() {
-            pixel.0.swap(0, 2);
-        }
-        self.image
-            .copy_from(&bgra, anchor.x() as _, anchor.y() as _)?;
+        self.match_dimensions();
+        let rgba = self.format.arrange(img);
+        self.image.render(anchor, &rgba)?;
+        self.damage
+            .add(anchor, (img.width(), img.height()), self.image.dimensions());
         Ok(())
     }
 
     fn blank(&mut self, _key: usize, anchor: Vector, size: Vector) -> RahmenResult<()> {
         let _t = crate::Timer::new(|e| debug!("Blanking {}ms", e.as_millis()));
-        self.match_dimensions()?;
-        let black =
-            image::FlatSamples::with_monocolor(&Rgba([0u8; 4]), size.x() as _, size.y() as _);
-        self.image.copy_from(
-            &black.as_view::<Rgba<u8>>().unwrap(),
-            anchor.x() as _,
-            anchor.y() as _,
-        )?;
+        self.match_dimensions();
+        self.image.blank(anchor, size)?;
+        self.damage.add(
+            anchor,
+            (size.x() as _, size.y() as _),
+            self.image.dimensions(),
+        );
         Ok(())
     }
 
     fn dimensions(&self) -> (u32, u32) {
-        (
-            self.framebuffer.var_screen_info.xres,
-            self.framebuffer.var_screen_info.yres,
-        )
+        let xres = self.framebuffer.var_screen_info.xres;
+        let yres = self.framebuffer.var_screen_info.yres;
+        if self.rotation.is_transposed() {
+            (yres, xres)
+        } else {
+            (xres, yres)
+        }
     }
 
     fn update(&mut self) -> RahmenResult<()> {
-        self.framebuffer
-            .frame
-            .as_mut()
-            .copy_from_slice(self.image.as_raw());
+        if !self.powered {
+            // keep the screen dark, the image is copied when powering on
+            return Ok(());
+        }
+        let _t = crate::Timer::new(|e| debug!("Updating {}ms", e.as_millis()));
+        let stride = self.framebuffer.fix_screen_info.line_length as usize;
+        self.format.copy_damage(
+            self.image.image(),
+            &self.damage.take(),
+            self.rotation,
+            self.dither,
+            self.framebuffer.frame.as_mut(),
+            stride,
+        );
+        Ok(())
+    }
+
+    fn power_off(&mut self) -> RahmenResult<()> {
+        self.powered = false;
+        // Black out the screen in case the driver does not support blanking
+        self.framebuffer.frame.fill(0);
+        if let Some(power) = &self.power {
+            power.power_off()?;
+        }
+        Ok(())
+    }
+
+    fn power_on(&mut self) -> RahmenResult<()> {
+        if let Some(power) = &self.power {
+            power.power_on()?;
+        }
+        self.powered = true;
+        if self.image.dimensions() == self.dimensions() {
+            // the frame was cleared when powering off
+            self.damage.add_all(self.image.dimensions());
+            self.update()?;
+        }
         Ok(())
     }
 }
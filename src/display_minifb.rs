@@ -9,7 +9,7 @@ use crate::display::Display;
 use crate::errors::{RahmenError, RahmenResult};
 
 use image::DynamicImage;
-use minifb::{Key, ScaleMode, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, ScaleMode, Window, WindowOptions};
 use std::time::Duration;
 
 fn window_err<E: std::fmt::Display>(e: E) -> RahmenError {
@@ -147,4 +147,19 @@ impl Display for MinifbDisplay {
         let (width, height) = self.window.get_size();
         (width as _, height as _)
     }
+
+    fn pressed_keys(&mut self) -> Vec<String> {
+        self.window
+            .get_keys_pressed(KeyRepeat::No)
+            .into_iter()
+            .map(key_name)
+            .collect()
+    }
+}
+
+/// Name a key like its Linux input event code, e.g. `KEY_RIGHT`
+fn key_name(key: Key) -> String {
+    let name = format!("{:?}", key).to_uppercase();
+    // digits are named `Key0` to `Key9` by minifb, but `KEY_0` to `KEY_9` by Linux
+    format!("KEY_{}", name.strip_prefix("KEY").unwrap_or(&name))
 }
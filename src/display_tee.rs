@@ -0,0 +1,171 @@
+//! Mirror the content of one display on further displays
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
+
+use crate::Vector;
+use crate::display::{Display, ShadowBuffer};
+use crate::errors::RahmenResult;
+use crate::ken_burns::fit;
+
+/// Displays mirroring another display, and a copy of the mirrored frame.
+///
+/// Mirrors with the same dimensions as the mirrored display receive the same render and blank
+/// operations. Mirrors with different dimensions receive the complete frame, scaled to fit and
+/// centered, on every update; the copy of the frame is only kept for them. A mirror failing to
+/// show the frame is disabled, the mirrored display continues.
+pub struct Mirrors {
+    displays: Vec<Box<dyn Display>>,
+    image: ShadowBuffer,
+}
+
+impl std::fmt::Debug for Mirrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mirrors")
+            .field("displays", &self.displays.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Mirrors {
+    /// Create new mirrors from a list of displays
+    pub fn new(displays: Vec<Box<dyn Display>>) -> Self {
+        Self {
+            displays,
+            image: Default::default(),
+        }
+    }
+
+    /// Whether there are no mirrors
+    pub fn is_empty(&self) -> bool {
+        self.displays.is_empty()
+    }
+
+    /// Whether a mirror shows the frame scaled from `dimensions`
+    fn scales(&self, dimensions: (u32, u32)) -> bool {
+        self.displays
+            .iter()
+            .any(|mirror| mirror.dimensions() != dimensions)
+    }
+}
+
+/// Apply `f` to all `mirrors`, disabling each mirror it fails for
+fn retain_working<F: FnMut(&mut dyn Display) -> RahmenResult<()>>(
+    mirrors: &mut Vec<Box<dyn Display>>,
+    mut f: F,
+) {
+    mirrors.retain_mut(|mirror| match f(mirror.as_mut()) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("Disabling a mirror: {}", err);
+            false
+        }
+    });
+}
+
+/// Show the complete frame `image` on a mirror with different dimensions
+fn show_scaled(image: &RgbaImage, display: &mut dyn Display) -> RahmenResult<()> {
+    let (width, height) = display.dimensions();
+    let (scaled_width, scaled_height) = fit(image.dimensions(), (width, height));
+    let scaled = image::imageops::resize(image, scaled_width, scaled_height, FilterType::Triangle);
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    canvas.copy_from(
+        &scaled,
+        (width - scaled_width) / 2,
+        (height - scaled_height) / 2,
+    )?;
+    display.render(0, Vector::zero(), &DynamicImage::ImageRgba8(canvas))?;
+    display.update()
+}
+
+/// A display forwarding all operations to another display and its [`Mirrors`]
+#[derive(Debug)]
+pub struct TeeDisplay<'a, D: Display + ?Sized> {
+    display: &'a mut D,
+    mirrors: &'a mut Mirrors,
+}
+
+impl<'a, D: Display + ?Sized> TeeDisplay<'a, D> {
+    /// Mirror `display` on `mirrors`
+    pub fn new(display: &'a mut D, mirrors: &'a mut Mirrors) -> Self {
+        Self { display, mirrors }
+    }
+
+    /// Apply `f` to all mirrors with the same dimensions as the mirrored display
+    fn for_each_matching<F: FnMut(&mut dyn Display) -> RahmenResult<()>>(&mut self, mut f: F) {
+        let dimensions = self.display.dimensions();
+        retain_working(&mut self.mirrors.displays, |mirror| {
+            if mirror.dimensions() == dimensions {
+                f(mirror)
+            } else {
+                Ok(())
+            }
+        });
+    }
+}
+
+impl<D: Display + ?Sized> Display for TeeDisplay<'_, D> {
+    fn render(&mut self, key: usize, anchor: Vector, img: &DynamicImage) -> RahmenResult<()> {
+        self.display.render(key, anchor, img)?;
+        let dimensions = self.display.dimensions();
+        if self.mirrors.scales(dimensions) {
+            self.mirrors.image.match_dimensions(dimensions);
+            self.mirrors.image.render(anchor, &img.to_rgba8())?;
+        }
+        self.for_each_matching(|mirror| mirror.render(key, anchor, img));
+        Ok(())
+    }
+
+    fn blank(&mut self, key: usize, anchor: Vector, size: Vector) -> RahmenResult<()> {
+        self.display.blank(key, anchor, size)?;
+        let dimensions = self.display.dimensions();
+        if self.mirrors.scales(dimensions) {
+            self.mirrors.image.match_dimensions(dimensions);
+            self.mirrors.image.blank(anchor, size)?;
+        }
+        self.for_each_matching(|mirror| mirror.blank(key, anchor, size));
+        Ok(())
+    }
+
+    fn update(&mut self) -> RahmenResult<()> {
+        self.display.update()?;
+        let dimensions = self.display.dimensions();
+        let Mirrors { displays, image } = &mut *self.mirrors;
+        retain_working(displays, |mirror| {
+            if mirror.dimensions() == dimensions {
+                mirror.update()
+            } else {
+                show_scaled(image.image(), mirror)
+            }
+        });
+        Ok(())
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.display.dimensions()
+    }
+
+    fn power_off(&mut self) -> RahmenResult<()> {
+        self.display.power_off()?;
+        for mirror in &mut self.mirrors.displays {
+            if let Err(err) = mirror.power_off() {
+                warn!("Failed to power off a mirror: {}", err);
+            }
+        }
+        Ok(())
+    }
+
+    fn power_on(&mut self) -> RahmenResult<()> {
+        self.display.power_on()?;
+        for mirror in &mut self.mirrors.displays {
+            if let Err(err) = mirror.power_on() {
+                warn!("Failed to power on a mirror: {}", err);
+            }
+        }
+        Ok(())
+    }
+
+    fn pressed_keys(&mut self) -> Vec<String> {
+        self.display.pressed_keys()
+    }
+}
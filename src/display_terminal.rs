@@ -0,0 +1,323 @@
+//! Functionality to render images inside a terminal emulator.
+//!
+//! This is meant for debugging, e.g. over SSH: it shows the composed frame using the Kitty
+//! graphics protocol or Sixel if the terminal supports them, or with Unicode half blocks
+//! otherwise. The display takes its size from the terminal.
+
+use std::io::{Cursor, Write};
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::Vector;
+use crate::display::{Display, ShadowBuffer};
+use crate::errors::RahmenResult;
+
+/// Cell size in pixels to assume if the terminal does not report its pixel dimensions
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+/// Maximum size of a base64-encoded chunk in the Kitty graphics protocol
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Protocol used to draw images in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalGraphics {
+    /// The Kitty graphics protocol, also supported by WezTerm and Ghostty
+    Kitty,
+    /// DEC Sixel graphics
+    Sixel,
+    /// Colored Unicode half blocks, two pixels per character cell
+    Blocks,
+}
+
+impl TerminalGraphics {
+    /// Guess the best protocol supported by the terminal from the environment
+    pub fn detect() -> Self {
+        let term = std::env::var("TERM").unwrap_or_default();
+        let program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || matches!(program.as_str(), "WezTerm" | "ghostty")
+        {
+            TerminalGraphics::Kitty
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || program == "mintty"
+        {
+            TerminalGraphics::Sixel
+        } else {
+            TerminalGraphics::Blocks
+        }
+    }
+}
+
+impl TryFrom<&str> for TerminalGraphics {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "kitty" => Ok(TerminalGraphics::Kitty),
+            "sixel" => Ok(TerminalGraphics::Sixel),
+            "blocks" => Ok(TerminalGraphics::Blocks),
+            _ => Err(format!("Unknown terminal graphics: {}", value)),
+        }
+    }
+}
+
+/// Terminal size in character cells and pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TerminalSize {
+    columns: u32,
+    rows: u32,
+    cell: (u32, u32),
+}
+
+impl TerminalSize {
+    fn query() -> Self {
+        match rustix::termios::tcgetwinsize(std::io::stdout()) {
+            Ok(size) if size.ws_col > 0 && size.ws_row > 0 => {
+                let columns = u32::from(size.ws_col);
+                let rows = u32::from(size.ws_row);
+                let cell = if size.ws_xpixel > 0 && size.ws_ypixel > 0 {
+                    (
+                        u32::from(size.ws_xpixel) / columns,
+                        u32::from(size.ws_ypixel) / rows,
+                    )
+                } else {
+                    DEFAULT_CELL_SIZE
+                };
+                Self {
+                    columns,
+                    rows,
+                    cell,
+                }
+            }
+            _ => Self {
+                columns: 80,
+                rows: 24,
+                cell: DEFAULT_CELL_SIZE,
+            },
+        }
+    }
+
+    /// Pixel dimensions of the area used for drawing. The last row is left empty so the terminal
+    /// does not scroll.
+    fn dimensions(&self) -> (u32, u32) {
+        (
+            (self.columns * self.cell.0).max(1),
+            (self.rows.saturating_sub(1) * self.cell.1).max(1),
+        )
+    }
+}
+
+/// A display driver drawing into the terminal on standard output.
+///
+/// The backing buffer is an [`RgbaImage`] with the terminal's pixel dimensions, which is encoded
+/// for the selected [`TerminalGraphics`] protocol on every update.
+#[derive(Debug)]
+pub struct TerminalDisplay {
+    graphics: TerminalGraphics,
+    size: TerminalSize,
+    image: ShadowBuffer,
+}
+
+impl TerminalDisplay {
+    /// Create a new terminal display, clearing the screen and hiding the cursor
+    pub fn new(graphics: TerminalGraphics) -> RahmenResult<Self> {
+        info!("Using terminal graphics {:?}", graphics);
+        let mut out = std::io::stdout().lock();
+        out.write_all(b"\x1b[?25l\x1b[2J")?;
+        out.flush()?;
+        Ok(Self {
+            graphics,
+            size: TerminalSize::query(),
+            image: Default::default(),
+        })
+    }
+
+    /// Enter the control loop. This will periodically trigger the callback, until it returns an
+    /// `Err` result.
+    pub fn main_loop<F: FnMut(&mut dyn Display) -> RahmenResult<()>>(&mut self, mut callback: F) {
+        while callback(self).is_ok() {
+            std::thread::sleep(Duration::from_millis(50));
+            let size = TerminalSize::query();
+            if size != self.size {
+                self.size = size;
+                // remove leftovers of the previous layout
+                let _ = std::io::stdout().lock().write_all(b"\x1b[2J");
+            }
+        }
+    }
+
+    /// Restore the terminal state, e.g. when terminating on a signal
+    pub fn restore() {
+        let mut out = std::io::stdout().lock();
+        let _ = out.write_all(b"\x1b_Ga=d,d=I,i=1,q=2\x1b\\\x1b[0m\x1b[2J\x1b[H\x1b[?25h");
+        let _ = out.flush();
+    }
+}
+
+impl Display for TerminalDisplay {
+    fn render(&mut self, _key: usize, anchor: Vector, img: &DynamicImage) -> RahmenResult<()> {
+        self.image.match_dimensions(self.dimensions());
+        self.image.render(anchor, &img.to_rgba8())
+    }
+
+    fn blank(&mut self, _key: usize, anchor: Vector, size: Vector) -> RahmenResult<()> {
+        self.image.match_dimensions(self.dimensions());
+        self.image.blank(anchor, size)
+    }
+
+    fn update(&mut self) -> RahmenResult<()> {
+        let _t = crate::Timer::new(|e| debug!("Updating {}ms", e.as_millis()));
+        self.image.match_dimensions(self.dimensions());
+        let encoded = match self.graphics {
+            TerminalGraphics::Kitty => encode_kitty(self.image.image())?,
+            TerminalGraphics::Sixel => encode_sixel(self.image.image()),
+            TerminalGraphics::Blocks => {
+                let rows = self.size.rows.saturating_sub(1).max(1);
+                let small = image::imageops::resize(
+                    self.image.image(),
+                    self.size.columns,
+                    rows * 2,
+                    FilterType::Triangle,
+                );
+                encode_blocks(&small)
+            }
+        };
+        let mut out = std::io::stdout().lock();
+        out.write_all(b"\x1b[H")?;
+        out.write_all(encoded.as_bytes())?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.size.dimensions()
+    }
+}
+
+impl Drop for TerminalDisplay {
+    fn drop(&mut self) {
+        Self::restore();
+    }
+}
+
+/// Encode an image as PNG and transmit it with the Kitty graphics protocol, replacing the image
+/// shown by the previous update
+fn encode_kitty(image: &RgbaImage) -> RahmenResult<String> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png)?;
+    let data = STANDARD.encode(png.into_inner());
+    let mut out = String::with_capacity(data.len() + data.len() / KITTY_CHUNK_SIZE * 16 + 64);
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK_SIZE).collect::<Vec<_>>();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        if index == 0 {
+            out.push_str(&format!("\x1b_Ga=T,f=100,i=1,q=2,C=1,m={};", more));
+        } else {
+            out.push_str(&format!("\x1b_Gm={};", more));
+        }
+        // base64 is ASCII
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\x1b\\");
+    }
+    Ok(out)
+}
+
+/// Encode an image as Sixel with a fixed palette of 6x6x6 colors
+pub fn encode_sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let level = |c: u8| (c as u16 * 5 + 127) / 255;
+    let index = |p: &Rgba<u8>| (level(p[0]) * 36 + level(p[1]) * 6 + level(p[2])) as usize;
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+    for i in 0..216 {
+        let percent = |l: usize| l * 100 / 5;
+        out.push_str(&format!(
+            "#{};2;{};{};{}",
+            i,
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        ));
+    }
+    let mut band = vec![0usize; width as usize * 6];
+    for top in (0..height).step_by(6) {
+        let rows = (height - top).min(6);
+        let mut used = [false; 216];
+        for y in 0..rows {
+            for x in 0..width {
+                let color = index(image.get_pixel(x, top + y));
+                band[y as usize * width as usize + x as usize] = color;
+                used[color] = true;
+            }
+        }
+        let mut first = true;
+        for color in (0..216).filter(|&c| used[c]) {
+            if !first {
+                out.push('$');
+            }
+            first = false;
+            out.push_str(&format!("#{}", color));
+            let mut run: Option<(u8, usize)> = None;
+            for x in 0..width as usize {
+                let bits = (0..rows as usize)
+                    .filter(|&y| band[y * width as usize + x] == color)
+                    .fold(0u8, |bits, y| bits | 1 << y);
+                run = match run {
+                    Some((previous, count)) if previous == bits => Some((bits, count + 1)),
+                    Some((previous, count)) => {
+                        push_sixel_run(&mut out, previous, count);
+                        Some((bits, 1))
+                    }
+                    None => Some((bits, 1)),
+                };
+            }
+            if let Some((bits, count)) = run {
+                push_sixel_run(&mut out, bits, count);
+            }
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+fn push_sixel_run(out: &mut String, bits: u8, count: usize) {
+    let c = char::from(63 + bits);
+    if count > 3 {
+        out.push_str(&format!("!{}{}", count, c));
+    } else {
+        (0..count).for_each(|_| out.push(c));
+    }
+}
+
+/// Encode an image as rows of upper half blocks, with the foreground color for the upper and the
+/// background color for the lower pixel of each character cell
+pub fn encode_blocks(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let mut out = String::new();
+    for top in (0..height).step_by(2) {
+        if top > 0 {
+            out.push_str("\x1b[0m\r\n");
+        }
+        for x in 0..width {
+            let upper = image.get_pixel(x, top);
+            let lower = if top + 1 < height {
+                *image.get_pixel(x, top + 1)
+            } else {
+                Rgba([0, 0, 0, 255])
+            };
+            out.push_str(&format!(
+                "\x1b[38;2;{};{};{};48;2;{};{};{}m\u{2580}",
+                upper[0], upper[1], upper[2], lower[0], lower[1], lower[2]
+            ));
+        }
+    }
+    out.push_str("\x1b[0m");
+    out
+}
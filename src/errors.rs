@@ -28,6 +28,8 @@ pub enum RahmenError {
     Exiv2Error(crate::exiv2::Exiv2Error),
     /// Pseudo-error to indicate program termination
     Terminate,
+    /// The display uses a pixel format that cannot be rendered to
+    UnsupportedPixelFormat(String),
     /// Errors from the windowed (minifb) display
     WindowError(String),
 }
@@ -48,6 +50,9 @@ impl fmt::Display for RahmenError {
             RahmenError::Retry => write!(f, "Retry"),
             RahmenError::Exiv2Error(err) => err.fmt(f),
             RahmenError::Terminate => write!(f, "Terminate"),
+            RahmenError::UnsupportedPixelFormat(err) => {
+                write!(f, "Unsupported pixel format: {}", err)
+            }
             RahmenError::WindowError(err) => write!(f, "Window error: {}", err),
         }
     }
@@ -66,6 +71,7 @@ impl Error for RahmenError {
             RahmenError::Retry => None,
             RahmenError::Exiv2Error(err) => err.source(),
             RahmenError::Terminate => None,
+            RahmenError::UnsupportedPixelFormat(_err) => None,
             RahmenError::WindowError(_err) => None,
         }
     }
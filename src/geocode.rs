@@ -0,0 +1,222 @@
+//! Offline reverse geocoding with [GeoNames](https://download.geonames.org/export/dump/) data.
+//!
+//! The cities are bucketed into cells of one degree, so looking up a position only measures the
+//! distance to the cities in the surrounding cells.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::config::GeoNames;
+use crate::errors::RahmenResult;
+
+/// Default for [`GeoNames::max_distance`] in km
+const DEFAULT_MAX_DISTANCE: f64 = 50.;
+
+/// Mean radius of the earth in km
+const EARTH_RADIUS: f64 = 6371.;
+
+/// Length of one degree of latitude in km
+const DEGREE: f64 = EARTH_RADIUS * std::f64::consts::PI / 180.;
+
+/// A city from the cities file
+#[derive(Debug, Clone)]
+struct City {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: String,
+    admin1_code: String,
+}
+
+/// The place nearest to a position, see [`Geocoder::nearest`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Place<'a> {
+    /// Name of the city
+    pub city: &'a str,
+    /// Name of the region, or its code without the admin1 file
+    pub region: &'a str,
+    /// Name of the country, or its code without the countries file
+    pub country: &'a str,
+    /// ISO 3166 code of the country, e.g. `CL`
+    pub country_code: &'a str,
+    /// Distance to the city in km
+    pub distance: f64,
+}
+
+/// Find the city nearest to a position
+#[derive(Debug)]
+pub struct Geocoder {
+    cities: Vec<City>,
+    /// Indices into `cities` by latitude and longitude cell
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// `admin1` code, e.g. `CL.12`, to region name
+    regions: HashMap<String, String>,
+    /// Country code to country name
+    countries: HashMap<String, String>,
+    max_distance: f64,
+}
+
+/// Great-circle distance between two positions in km
+pub fn distance(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let (phi1, phi2) = (latitude1.to_radians(), latitude2.to_radians());
+    let delta_phi = phi2 - phi1;
+    let delta_lambda = (longitude2 - longitude1).to_radians();
+    let a = (delta_phi / 2.).sin().powi(2)
+        + phi1.cos() * phi2.cos() * (delta_lambda / 2.).sin().powi(2);
+    2. * EARTH_RADIUS * a.sqrt().min(1.).asin()
+}
+
+/// The cell of a position
+fn cell(latitude: f64, longitude: f64) -> (i32, i32) {
+    (latitude.floor() as i32, longitude.floor() as i32)
+}
+
+/// Read the tab-separated lines of a GeoNames file, skipping comments
+fn read_lines<R: BufRead, F: FnMut(usize, Vec<&str>) -> RahmenResult<()>>(
+    reader: R,
+    mut f: F,
+) -> RahmenResult<()> {
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        f(index + 1, line.split('\t').collect())?;
+    }
+    Ok(())
+}
+
+impl Geocoder {
+    /// Load the files given in the settings
+    pub fn open(settings: &GeoNames) -> RahmenResult<Self> {
+        let open = |path: &str| -> RahmenResult<BufReader<File>> {
+            File::open(path)
+                .map(BufReader::new)
+                .map_err(|err| std::io::Error::new(err.kind(), format!("{}: {}", path, err)).into())
+        };
+        Self::from_readers(
+            open(&settings.cities)?,
+            settings.admin1.as_deref().map(open).transpose()?,
+            settings.countries.as_deref().map(open).transpose()?,
+            settings.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE),
+        )
+    }
+
+    /// Read a cities file, e.g. `cities15000.txt`, and optionally `admin1CodesASCII.txt` and
+    /// `countryInfo.txt` for the names of the regions and countries
+    pub fn from_readers<C: BufRead, A: BufRead, N: BufRead>(
+        cities: C,
+        admin1: Option<A>,
+        countries: Option<N>,
+        max_distance: f64,
+    ) -> RahmenResult<Self> {
+        let mut geocoder = Self {
+            cities: vec![],
+            cells: HashMap::new(),
+            regions: HashMap::new(),
+            countries: HashMap::new(),
+            max_distance,
+        };
+        read_lines(cities, |line, fields| {
+            let invalid = || {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("cities file line {}: expected the GeoNames format", line),
+                )
+            };
+            if fields.len() < 11 {
+                return Err(invalid().into());
+            }
+            let latitude: f64 = fields[4].parse().map_err(|_| invalid())?;
+            let longitude: f64 = fields[5].parse().map_err(|_| invalid())?;
+            geocoder
+                .cells
+                .entry(cell(latitude, longitude))
+                .or_default()
+                .push(geocoder.cities.len());
+            geocoder.cities.push(City {
+                name: fields[1].to_string(),
+                latitude,
+                longitude,
+                country_code: fields[8].to_string(),
+                admin1_code: fields[10].to_string(),
+            });
+            Ok(())
+        })?;
+        if let Some(admin1) = admin1 {
+            read_lines(admin1, |_, fields| {
+                if let [code, name, ..] = fields[..] {
+                    geocoder.regions.insert(code.to_string(), name.to_string());
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(countries) = countries {
+            read_lines(countries, |_, fields| {
+                if let [code, _, _, _, name, ..] = fields[..] {
+                    geocoder
+                        .countries
+                        .insert(code.to_string(), name.to_string());
+                }
+                Ok(())
+            })?;
+        }
+        Ok(geocoder)
+    }
+
+    /// The number of cities
+    pub fn len(&self) -> usize {
+        self.cities.len()
+    }
+
+    /// Whether there are no cities
+    pub fn is_empty(&self) -> bool {
+        self.cities.is_empty()
+    }
+
+    /// The city nearest to the position, if there is one within the maximum distance
+    pub fn nearest(&self, latitude: f64, longitude: f64) -> Option<Place<'_>> {
+        // the cells which may contain cities within the maximum distance
+        let latitude_cells = (self.max_distance / DEGREE).ceil() as i32;
+        let farthest_latitude = (latitude.abs() + self.max_distance / DEGREE).min(90.);
+        let longitude_cells = (self.max_distance / (DEGREE * farthest_latitude.to_radians().cos()))
+            .ceil()
+            .min(180.) as i32;
+        let (center_latitude, center_longitude) = cell(latitude, longitude);
+        let mut nearest: Option<(f64, &City)> = None;
+        for cell_latitude in center_latitude - latitude_cells..=center_latitude + latitude_cells {
+            for offset in -longitude_cells..=longitude_cells.min(179) {
+                // wrap around the antimeridian
+                let cell_longitude = (center_longitude + offset + 180).rem_euclid(360) - 180;
+                let Some(indices) = self.cells.get(&(cell_latitude, cell_longitude)) else {
+                    continue;
+                };
+                for city in indices.iter().map(|index| &self.cities[*index]) {
+                    let distance = distance(latitude, longitude, city.latitude, city.longitude);
+                    if distance <= self.max_distance
+                        && nearest.is_none_or(|(nearest, _)| distance < nearest)
+                    {
+                        nearest = Some((distance, city));
+                    }
+                }
+            }
+        }
+        nearest.map(|(distance, city)| {
+            let region_code = format!("{}.{}", city.country_code, city.admin1_code);
+            Place {
+                city: &city.name,
+                region: self
+                    .regions
+                    .get(&region_code)
+                    .map_or(&city.admin1_code, |region| region),
+                country: self
+                    .countries
+                    .get(&city.country_code)
+                    .map_or(&city.country_code, |country| country),
+                country_code: &city.country_code,
+                distance,
+            }
+        })
+    }
+}
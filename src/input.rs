@@ -0,0 +1,115 @@
+//! Map keys from keyboards and remote controls to actions.
+//!
+//! Keys are named like the Linux input event codes, e.g. `KEY_RIGHT` or `KEY_PLAYPAUSE`. With the
+//! `evdev` feature, keys are read from `/dev/input/event*` devices. Displays can report their own
+//! key presses through [`crate::display::Display::pressed_keys`].
+
+use std::collections::HashMap;
+
+use crate::config::Action;
+
+/// Mapping from key names to actions
+#[derive(Debug, Clone)]
+pub struct KeyMap(HashMap<String, Action>);
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let keys = [
+            ("KEY_RIGHT", Action::Next),
+            ("KEY_PAGEDOWN", Action::Next),
+            ("KEY_NEXTSONG", Action::Next),
+            ("KEY_LEFT", Action::Previous),
+            ("KEY_PAGEUP", Action::Previous),
+            ("KEY_PREVIOUSSONG", Action::Previous),
+            ("KEY_SPACE", Action::Pause),
+            ("KEY_PLAYPAUSE", Action::Pause),
+            ("KEY_PAUSE", Action::Pause),
+            ("KEY_S", Action::StatusLine),
+            ("KEY_I", Action::Info),
+            ("KEY_INFO", Action::Info),
+            ("KEY_B", Action::Blank),
+            ("KEY_SLEEP", Action::Blank),
+        ];
+        Self(
+            keys.into_iter()
+                .map(|(key, action)| (key.to_string(), action))
+                .collect(),
+        )
+    }
+}
+
+impl KeyMap {
+    /// Create a key map from the configured keys, or the default mapping
+    pub fn new(keys: Option<HashMap<String, Action>>) -> Self {
+        keys.map_or_else(Default::default, Self)
+    }
+
+    /// The action mapped to the key `name`, if any
+    pub fn action(&self, name: &str) -> Option<Action> {
+        self.0.get(name).copied()
+    }
+
+    /// Names of all mapped keys
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+/// Read key presses from input devices in background threads, and send the names of the pressed
+/// keys to `sender`. Without `devices`, all devices supporting any of the keys of `key_map` are
+/// read.
+#[cfg(feature = "evdev")]
+pub fn read_devices(
+    devices: Option<&[String]>,
+    key_map: &KeyMap,
+    sender: std::sync::mpsc::Sender<String>,
+) -> crate::errors::RahmenResult<()> {
+    use std::str::FromStr;
+
+    let devices = match devices {
+        Some(paths) => paths
+            .iter()
+            .map(|path| Ok((path.into(), evdev::Device::open(path)?)))
+            .collect::<crate::errors::RahmenResult<Vec<(std::path::PathBuf, _)>>>()?,
+        None => {
+            let codes = key_map
+                .keys()
+                .flat_map(|key| evdev::KeyCode::from_str(key).ok())
+                .collect::<Vec<_>>();
+            evdev::enumerate()
+                .filter(|(_, device)| {
+                    device
+                        .supported_keys()
+                        .is_some_and(|keys| codes.iter().any(|code| keys.contains(*code)))
+                })
+                .collect()
+        }
+    };
+    if devices.is_empty() {
+        warn!("No input devices found");
+    }
+    for (path, mut device) in devices {
+        info!("Reading input from {:?} ({:?})", path, device.name());
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            loop {
+                let events = match device.fetch_events() {
+                    Ok(events) => events,
+                    Err(err) => {
+                        warn!("Failed to read input from {:?}: {}", path, err);
+                        return;
+                    }
+                };
+                for event in events {
+                    // only key presses, no releases or repeats
+                    if let evdev::EventSummary::Key(_, code, 1) = event.destructure() {
+                        if sender.send(format!("{:?}", code)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+    }
+    Ok(())
+}
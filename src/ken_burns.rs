@@ -0,0 +1,141 @@
+//! Geometry for the Ken Burns pan-and-zoom effect
+
+use std::ffi::OsStr;
+
+use crate::config::Focus;
+use crate::exiv2::Metadata;
+
+/// A view into the working image, in coordinates relative to its size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    /// Horizontal center, from 0 to 1
+    pub x: f32,
+    /// Vertical center, from 0 to 1
+    pub y: f32,
+    /// Fraction of the working image covered by the view, from `1 / zoom` to 1
+    pub scale: f32,
+}
+
+impl View {
+    /// Compute the pixel rectangle `(x, y, width, height)` of this view within an image of the
+    /// given dimensions. The rectangle is kept inside the image.
+    pub fn to_rect(&self, dimensions: (u32, u32)) -> (u32, u32, u32, u32) {
+        let width = ((dimensions.0 as f32 * self.scale).round() as u32).clamp(1, dimensions.0);
+        let height = ((dimensions.1 as f32 * self.scale).round() as u32).clamp(1, dimensions.1);
+        let x = (self.x * dimensions.0 as f32 - width as f32 / 2.)
+            .clamp(0., (dimensions.0 - width) as f32);
+        let y = (self.y * dimensions.1 as f32 - height as f32 / 2.)
+            .clamp(0., (dimensions.1 - height) as f32);
+        (x as u32, y as u32, width, height)
+    }
+}
+
+/// Motion across an image, from a start view to an end view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pan {
+    /// View at the beginning of the slide
+    pub start: View,
+    /// View at the end of the slide
+    pub end: View,
+}
+
+impl Pan {
+    /// Choose a motion zooming by `zoom` into the focal point, or a random motion if there is no
+    /// focal point
+    pub fn choose(focal_point: Option<(f32, f32)>, zoom: f32) -> Self {
+        let full = View {
+            x: 0.5,
+            y: 0.5,
+            scale: 1.,
+        };
+        match focal_point {
+            Some((x, y)) => Self {
+                start: full,
+                end: View {
+                    x,
+                    y,
+                    scale: 1. / zoom,
+                },
+            },
+            None => {
+                let close = View {
+                    x: fastrand::f32(),
+                    y: fastrand::f32(),
+                    scale: 1. / zoom,
+                };
+                let wide = View {
+                    x: fastrand::f32(),
+                    y: fastrand::f32(),
+                    scale: 1. - (1. - 1. / zoom) * fastrand::f32() / 2.,
+                };
+                // zoom in or out, with equal probability
+                if fastrand::bool() {
+                    Self {
+                        start: wide,
+                        end: close,
+                    }
+                } else {
+                    Self {
+                        start: close,
+                        end: wide,
+                    }
+                }
+            }
+        }
+    }
+
+    /// The view at `progress`, ranging from 0 (start) to 1 (end)
+    pub fn view_at(&self, progress: f32) -> View {
+        let progress = progress.clamp(0., 1.);
+        let lerp = |a: f32, b: f32| a + (b - a) * progress;
+        View {
+            x: lerp(self.start.x, self.end.x),
+            y: lerp(self.start.y, self.end.y),
+            scale: lerp(self.start.scale, self.end.scale),
+        }
+    }
+}
+
+/// Compute the dimensions of an image of size `dimensions` scaled to fit into `bounds`,
+/// preserving its aspect ratio
+pub fn fit(dimensions: (u32, u32), bounds: (u32, u32)) -> (u32, u32) {
+    let ratio = f64::min(
+        bounds.0 as f64 / dimensions.0.max(1) as f64,
+        bounds.1 as f64 / dimensions.1.max(1) as f64,
+    );
+    (
+        ((dimensions.0 as f64 * ratio).round() as u32).clamp(1, bounds.0.max(1)),
+        ((dimensions.1 as f64 * ratio).round() as u32).clamp(1, bounds.1.max(1)),
+    )
+}
+
+/// Determine the focal point of the image at `path`, relative to its size. Returns `None` if the
+/// motion should be chosen at random.
+pub fn focal_point<P: AsRef<OsStr>>(focus: Focus, path: P) -> Option<(f32, f32)> {
+    match focus {
+        Focus::Random => None,
+        Focus::Center => Some((0.5, 0.5)),
+        Focus::Subject => Some(subject_area(path).unwrap_or((0.5, 0.5))),
+    }
+}
+
+/// Read the center of the `Exif.Photo.SubjectArea` tag, relative to the image dimensions
+fn subject_area<P: AsRef<OsStr>>(path: P) -> Option<(f32, f32)> {
+    let metadata = Metadata::new_from_path(path).ok()?;
+    let tag = |key| metadata.get_tag_interpreted_string(key).ok();
+    let area = tag("Exif.Photo.SubjectArea")?
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<f32>, _>>()
+        .ok()?;
+    let width: f32 = tag("Exif.Photo.PixelXDimension")?.parse().ok()?;
+    let height: f32 = tag("Exif.Photo.PixelYDimension")?.parse().ok()?;
+    // The subject is a point, circle or rectangle, all of which start with the center coordinates
+    if area.len() < 2 || width <= 0. || height <= 0. {
+        return None;
+    }
+    Some((
+        (area[0] / width).clamp(0., 1.),
+        (area[1] / height).clamp(0., 1.),
+    ))
+}
@@ -22,20 +22,44 @@ extern crate log;
 use pathfinder_geometry::vector::Vector2I;
 use std::time::{Duration, Instant};
 
+pub mod check;
 pub mod config;
+pub mod control;
 pub mod dataflow;
 pub mod display;
+#[cfg(feature = "drm")]
+pub mod display_drm;
+pub mod display_file;
 pub mod display_framebuffer;
 #[cfg(feature = "minifb")]
 pub mod display_minifb;
+pub mod display_tee;
+#[cfg(feature = "terminal")]
+pub mod display_terminal;
 pub mod errors;
 pub mod font;
+pub mod geocode;
+pub mod input;
+pub mod ken_burns;
+pub mod mat;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod power;
 
 /// Metadata access backed by libexiv2, re-exported as a module path.
 pub use rahmen_exiv2 as exiv2;
 pub mod provider;
 pub mod provider_glob;
 pub mod provider_list;
+#[cfg(feature = "http")]
+pub mod remote;
+pub mod schedule;
+#[cfg(unix)]
+pub mod signals;
+#[cfg(feature = "http")]
+pub mod stream;
+pub mod tags;
+pub mod watch;
 
 /// A timer to track the duration of a code span until it is dropped
 #[derive(Debug)]
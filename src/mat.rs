@@ -0,0 +1,128 @@
+//! Compose images onto a mat (passe-partout) with an optional border and drop shadow
+
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+
+use crate::Vector;
+use crate::config::{Border, Color, Mat};
+use crate::errors::RahmenResult;
+use crate::provider::load_image_from_path;
+
+/// Default mat color, an off-white
+const DEFAULT_MAT_COLOR: Color = Color([240, 237, 229]);
+
+/// A mat with an optional border and drop shadow to place images on
+#[derive(Debug)]
+pub struct Passepartout {
+    color: Rgb<u8>,
+    texture: Option<RgbImage>,
+    mat_width: u32,
+    border_width: u32,
+    border_color: Rgb<u8>,
+    shadow: u32,
+}
+
+impl Passepartout {
+    /// Create a new passepartout from the mat and border settings, loading the mat texture if
+    /// configured
+    pub fn new(mat: Option<Mat>, border: Option<Border>) -> RahmenResult<Self> {
+        let mat = mat.unwrap_or_default();
+        let border = border.unwrap_or_default();
+        let texture = if let Some(path) = mat.texture {
+            Some(load_image_from_path(path, None)?.to_rgb8())
+        } else {
+            None
+        };
+        Ok(Self {
+            color: Rgb(mat.color.unwrap_or(DEFAULT_MAT_COLOR).0),
+            texture,
+            mat_width: mat.width.unwrap_or(0),
+            border_width: border.width.unwrap_or(0),
+            border_color: Rgb(border.color.unwrap_or(Color([0, 0, 0])).0),
+            shadow: border.shadow.unwrap_or(0),
+        })
+    }
+
+    /// Space to reserve on each side of the image for the mat and the border
+    pub fn inset(&self) -> u32 {
+        self.mat_width + self.border_width
+    }
+
+    /// Compose an image onto a mat of size `area`. The `anchor` of the image is relative to the
+    /// area without the inset on each side.
+    pub fn compose(&self, area: (u32, u32), anchor: Vector, img: &DynamicImage) -> DynamicImage {
+        let background = self.background(area, anchor, img.dimensions());
+        self.place(background, anchor, img)
+    }
+
+    /// Draw the mat of size `area` with the border and shadow around an image of `size` at
+    /// `anchor`, without the image. The background stays the same while only the content of the
+    /// image changes, e.g. during a Ken Burns pan.
+    pub fn background(&self, area: (u32, u32), anchor: Vector, size: (u32, u32)) -> RgbImage {
+        let mut canvas = match &self.texture {
+            Some(texture) => RgbImage::from_fn(area.0, area.1, |x, y| {
+                *texture.get_pixel(x % texture.width(), y % texture.height())
+            }),
+            None => RgbImage::from_pixel(area.0, area.1, self.color),
+        };
+        let (x, y) = self.position(anchor);
+        let border = self.border_width as i64;
+        let framed_width = size.0 as i64 + 2 * border;
+        let framed_height = size.1 as i64 + 2 * border;
+        if self.shadow > 0 {
+            let shadow = self.shadow as i64;
+            fill(
+                &mut canvas,
+                (x - border + shadow, y - border + shadow),
+                (framed_width, framed_height),
+                |p| Rgb(p.0.map(|c| c / 2)),
+            );
+        }
+        if border > 0 {
+            fill(
+                &mut canvas,
+                (x - border, y - border),
+                (framed_width, framed_height),
+                |_| self.border_color,
+            );
+        }
+        canvas
+    }
+
+    /// Place an image at `anchor` onto a `background` drawn for it
+    pub fn place(
+        &self,
+        mut background: RgbImage,
+        anchor: Vector,
+        img: &DynamicImage,
+    ) -> DynamicImage {
+        let (x, y) = self.position(anchor);
+        match img {
+            DynamicImage::ImageRgb8(rgb) => image::imageops::replace(&mut background, rgb, x, y),
+            img => image::imageops::replace(&mut background, &img.to_rgb8(), x, y),
+        }
+        DynamicImage::ImageRgb8(background)
+    }
+
+    /// Position of the image on the canvas
+    fn position(&self, anchor: Vector) -> (i64, i64) {
+        let inset = self.inset() as i64;
+        (anchor.x() as i64 + inset, anchor.y() as i64 + inset)
+    }
+}
+
+/// Apply `f` to all pixels of the rectangle at `origin` with `size`, clipped to the canvas
+fn fill<F: Fn(&Rgb<u8>) -> Rgb<u8>>(
+    canvas: &mut RgbImage,
+    origin: (i64, i64),
+    size: (i64, i64),
+    f: F,
+) {
+    let x_range = origin.0.max(0)..(origin.0 + size.0).min(canvas.width() as i64);
+    let y_range = origin.1.max(0)..(origin.1 + size.1).min(canvas.height() as i64);
+    for y in y_range {
+        for x in x_range.clone() {
+            let pixel = canvas.get_pixel_mut(x as u32, y as u32);
+            *pixel = f(pixel);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Publish the state of the slideshow to an MQTT broker and receive commands from it, e.g. to
+//! integrate the frame into Home Assistant.
+//!
+//! Below the configured topic prefix, e.g. `rahmen`, the state is published to retained topics:
+//! `rahmen/path` and `rahmen/caption` of the current image, `rahmen/power` and `rahmen/paused` as
+//! `ON` or `OFF`, `rahmen/brightness` from 0 to 100, and `rahmen/availability` as `online` or
+//! `offline`. Commands are received on `rahmen/next/set`, `rahmen/previous/set`,
+//! `rahmen/pause/set`, `rahmen/power/set`, `rahmen/brightness/set` and `rahmen/schedule/set`, and
+//! JSON commands of the [`crate::control`] protocol on `rahmen/command`.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+
+use crate::config::Mqtt;
+use crate::control::{self, Command, Status};
+use crate::errors::RahmenResult;
+
+/// How often to check the state of the slideshow for changes
+const STATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time to wait before reconnecting to the broker
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// The prefix of the state and command topics
+pub fn base_topic(config: &Mqtt) -> &str {
+    config.topic.as_deref().unwrap_or("rahmen")
+}
+
+fn client_id(config: &Mqtt) -> &str {
+    config.client_id.as_deref().unwrap_or("rahmen")
+}
+
+/// Translate a message received on `topic` into a command
+pub fn parse_command(base: &str, topic: &str, payload: &str) -> Result<Command, String> {
+    let name = topic
+        .strip_prefix(base)
+        .and_then(|topic| topic.strip_prefix('/'))
+        .ok_or_else(|| format!("Unexpected topic: {}", topic))?;
+    let payload = payload.trim();
+    let switch = |payload: &str| match payload {
+        "ON" => Ok(true),
+        "OFF" => Ok(false),
+        _ => Err(format!("Expected ON or OFF, got {}", payload)),
+    };
+    match name {
+        "command" => serde_json::from_str(payload).map_err(|err| err.to_string()),
+        "next/set" => Ok(Command::Next),
+        "previous/set" => Ok(Command::Previous),
+        "pause/set" => Ok(if switch(payload)? {
+            Command::Pause
+        } else {
+            Command::Resume
+        }),
+        "power/set" => Ok(Command::Power {
+            on: switch(payload)?,
+        }),
+        "brightness/set" => {
+            let brightness = payload
+                .parse::<f32>()
+                .map_err(|err| format!("Invalid brightness {}: {}", payload, err))?;
+            Ok(Command::SetBrightness {
+                brightness: Some((brightness / 100.).clamp(0., 1.)),
+            })
+        }
+        // clear the brightness set on request, following the dimming schedule again
+        "schedule/set" => Ok(Command::SetBrightness { brightness: None }),
+        _ => Err(format!("Unknown command topic: {}", topic)),
+    }
+}
+
+/// Home Assistant discovery messages as pairs of topic and payload
+pub fn discovery_messages(config: &Mqtt) -> Vec<(String, String)> {
+    let base = base_topic(config);
+    let prefix = config
+        .discovery_prefix
+        .as_deref()
+        .unwrap_or("homeassistant");
+    // discovery ids may only contain alphanumerics, underscores and hyphens
+    let node = client_id(config)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let device = json!({
+        "identifiers": [node],
+        "name": "Rahmen",
+        "model": "Rahmen",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let component = |component: &str, object: &str, mut config: serde_json::Value| {
+        config["unique_id"] = json!(format!("{}_{}", node, object));
+        config["availability_topic"] = json!(format!("{}/availability", base));
+        config["device"] = device.clone();
+        (
+            format!("{}/{}/{}/{}/config", prefix, component, node, object),
+            config.to_string(),
+        )
+    };
+    vec![
+        component(
+            "light",
+            "screen",
+            json!({
+                "name": "Screen",
+                "command_topic": format!("{}/power/set", base),
+                "state_topic": format!("{}/power", base),
+                "brightness_command_topic": format!("{}/brightness/set", base),
+                "brightness_state_topic": format!("{}/brightness", base),
+                "brightness_scale": 100,
+            }),
+        ),
+        component(
+            "switch",
+            "pause",
+            json!({
+                "name": "Pause",
+                "command_topic": format!("{}/pause/set", base),
+                "state_topic": format!("{}/paused", base),
+            }),
+        ),
+        component(
+            "button",
+            "next",
+            json!({ "name": "Next", "command_topic": format!("{}/next/set", base) }),
+        ),
+        component(
+            "button",
+            "previous",
+            json!({ "name": "Previous", "command_topic": format!("{}/previous/set", base) }),
+        ),
+        component(
+            "button",
+            "schedule",
+            json!({
+                "name": "Dimming schedule",
+                "command_topic": format!("{}/schedule/set", base),
+            }),
+        ),
+        component(
+            "sensor",
+            "caption",
+            json!({ "name": "Caption", "state_topic": format!("{}/caption", base) }),
+        ),
+        component(
+            "sensor",
+            "path",
+            json!({ "name": "Path", "state_topic": format!("{}/path", base) }),
+        ),
+    ]
+}
+
+/// State topics and their payloads for `status`
+fn state_messages(status: &Status) -> [(&'static str, String); 5] {
+    let switch = |on: bool| if on { "ON" } else { "OFF" }.to_string();
+    [
+        ("path", status.path.clone().unwrap_or_default()),
+        ("caption", status.caption.clone().unwrap_or_default()),
+        ("power", switch(status.powered)),
+        ("paused", switch(status.paused)),
+        ("brightness", (status.brightness * 100.).round().to_string()),
+    ]
+}
+
+/// Connect to the broker in background threads, publishing the state of the slideshow and
+/// sending the commands received to `requests`. The connection is reestablished if it fails.
+pub fn start(config: &Mqtt, requests: Sender<control::Request>) -> RahmenResult<()> {
+    let base = base_topic(config).to_string();
+    let availability = format!("{}/availability", base);
+    let mut options = MqttOptions::new(
+        client_id(config),
+        config.host.clone(),
+        config.port.unwrap_or(1883),
+    );
+    options
+        .set_keep_alive(Duration::from_secs(30))
+        .set_last_will(LastWill::new(
+            &availability,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+    let discovery = if config.discovery.unwrap_or(true) {
+        discovery_messages(config)
+    } else {
+        vec![]
+    };
+    let (client, mut connection) = Client::new(options, 16);
+    info!("Connecting to MQTT broker {}", config.host);
+
+    // publish everything again after reconnecting, the broker may have lost retained messages
+    let republish = Arc::new(AtomicBool::new(true));
+    let publisher = client.clone();
+    let state_requests = requests.clone();
+    let state_base = base.clone();
+    let state_republish = Arc::clone(&republish);
+    std::thread::spawn(move || {
+        let mut published: Option<Status> = None;
+        loop {
+            let reply = control::submit_command(Command::Status, &state_requests);
+            let Some(status) = reply.status else {
+                // the slideshow terminated
+                return;
+            };
+            if state_republish.swap(false, Ordering::Relaxed) {
+                published = None;
+            }
+            let previous = published.as_ref().map(state_messages);
+            for (index, (name, payload)) in state_messages(&status).into_iter().enumerate() {
+                if previous
+                    .as_ref()
+                    .is_some_and(|previous| previous[index].1 == payload)
+                {
+                    continue;
+                }
+                let topic = format!("{}/{}", state_base, name);
+                if let Err(err) = publisher.publish(topic, QoS::AtLeastOnce, true, payload) {
+                    warn!("Failed to publish the state: {}", err);
+                }
+            }
+            published = Some(status);
+            std::thread::sleep(STATE_INTERVAL);
+        }
+    });
+
+    std::thread::spawn(move || {
+        for notification in connection.iter() {
+            match notification {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    info!("Connected to MQTT broker");
+                    let subscriptions = [format!("{}/command", base), format!("{}/+/set", base)];
+                    for topic in subscriptions {
+                        if let Err(err) = client.subscribe(topic, QoS::AtLeastOnce) {
+                            warn!("Failed to subscribe: {}", err);
+                        }
+                    }
+                    for (topic, payload) in &discovery {
+                        if let Err(err) =
+                            client.publish(topic, QoS::AtLeastOnce, true, payload.clone())
+                        {
+                            warn!("Failed to publish discovery: {}", err);
+                        }
+                    }
+                    if let Err(err) =
+                        client.publish(&availability, QoS::AtLeastOnce, true, "online")
+                    {
+                        warn!("Failed to publish availability: {}", err);
+                    }
+                    republish.store(true, Ordering::Relaxed);
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    let payload = String::from_utf8_lossy(&publish.payload);
+                    match parse_command(&base, &publish.topic, &payload) {
+                        Ok(command) => {
+                            let reply = control::submit_command(command, &requests);
+                            if let Some(error) = reply.error {
+                                warn!("MQTT command failed: {}", error);
+                            }
+                        }
+                        Err(err) => warn!("Ignoring MQTT message: {}", err),
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    warn!("MQTT connection failed: {}", err);
+                    std::thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Display power management through sysfs
+
+use std::path::{Path, PathBuf};
+
+use crate::errors::RahmenResult;
+
+/// Value to write to power down the display (`FB_BLANK_POWERDOWN`)
+const POWER_DOWN: &str = "4";
+/// Value to write to power up the display (`FB_BLANK_UNBLANK`)
+const UNBLANK: &str = "0";
+
+/// Switches a display's power through a sysfs attribute, such as
+/// `/sys/class/graphics/fb0/blank` or `/sys/class/backlight/*/bl_power`
+#[derive(Debug, Clone)]
+pub struct SysfsPower {
+    path: PathBuf,
+}
+
+impl SysfsPower {
+    /// Create a new power switch writing to the sysfs attribute at `path`
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Derive the blanking attribute for a framebuffer device, e.g. `/dev/fb1` maps to
+    /// `/sys/class/graphics/fb1/blank`
+    pub fn for_framebuffer<P: AsRef<Path>>(device: P) -> Option<Self> {
+        let name = device.as_ref().file_name()?.to_str()?;
+        if name.starts_with("fb") {
+            Some(Self::new(format!("/sys/class/graphics/{}/blank", name)))
+        } else {
+            None
+        }
+    }
+
+    /// Path of the sysfs attribute
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Put the display into power-save mode
+    pub fn power_off(&self) -> RahmenResult<()> {
+        std::fs::write(&self.path, POWER_DOWN)?;
+        Ok(())
+    }
+
+    /// Wake the display from power-save mode
+    pub fn power_on(&self) -> RahmenResult<()> {
+        std::fs::write(&self.path, UNBLANK)?;
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 //! Utilities to provide images, and other abstractions
 
+use std::collections::VecDeque;
 use std::convert::{TryFrom, TryInto};
 use std::io::BufReader;
 use std::path::Path;
+use std::sync::Arc;
 
 use convert_case::{Case, Casing};
 use image::{DynamicImage, RgbImage};
@@ -12,9 +14,11 @@ use pyo3::types::PyList;
 use regex::Regex;
 use serde::Deserialize;
 
-use crate::config::{Element, Replacement};
+use crate::config::{Element, GpsFormat, Replacement};
 use crate::errors::{RahmenError, RahmenResult};
 use crate::exiv2::Metadata;
+use crate::geocode::Geocoder;
+use crate::tags;
 
 /// Provider trait to produce images, or other types
 pub trait Provider<D> {
@@ -31,6 +35,73 @@ impl<D> Provider<D> for Box<dyn Provider<D>> {
     }
 }
 
+/// Number of elements a `History` remembers
+const HISTORY_LENGTH: usize = 100;
+
+/// A provider remembering the elements shown, to go back and forth between them
+#[derive(Debug)]
+pub struct History<D, P: Provider<D>> {
+    provider: P,
+    shown: VecDeque<D>,
+    /// Index of the current element in `shown`
+    position: usize,
+    /// Number of elements dropped from the front of `shown`
+    forgotten: usize,
+}
+
+impl<D: Clone, P: Provider<D>> History<D, P> {
+    /// Create a new history of the elements from `provider`
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            shown: VecDeque::new(),
+            position: 0,
+            forgotten: 0,
+        }
+    }
+
+    /// Go back to the previous element. Returns the first element again at the beginning of the
+    /// history, and `None` if there is no element yet.
+    pub fn previous_image(&mut self) -> Option<D> {
+        self.position = self.position.saturating_sub(1);
+        self.shown.get(self.position).cloned()
+    }
+
+    /// Show `element` out of order. It is appended to the history.
+    pub fn goto(&mut self, element: D) -> D {
+        self.push(element.clone());
+        element
+    }
+
+    /// Index of the current element, counting all elements shown since the start
+    pub fn index(&self) -> usize {
+        self.forgotten + self.position
+    }
+
+    fn push(&mut self, element: D) {
+        if self.shown.len() == HISTORY_LENGTH {
+            self.shown.pop_front();
+            self.forgotten += 1;
+        }
+        self.shown.push_back(element);
+        self.position = self.shown.len() - 1;
+    }
+}
+
+impl<D: Clone, P: Provider<D>> Provider<D> for History<D, P> {
+    fn next_image(&mut self) -> RahmenResult<Option<D>> {
+        if self.position + 1 < self.shown.len() {
+            self.position += 1;
+            return Ok(self.shown.get(self.position).cloned());
+        }
+        let next = self.provider.next_image()?;
+        if let Some(element) = &next {
+            self.push(element.clone());
+        }
+        Ok(next)
+    }
+}
+
 fn load_jpeg<P: AsRef<Path>>(path: P, max_size: Option<usize>) -> RahmenResult<DynamicImage> {
     let mut d = mozjpeg::Decompress::with_markers(mozjpeg::ALL_MARKERS).from_path(&path)?;
 
@@ -113,6 +184,17 @@ impl StatusLineTransformation {
     }
 }
 
+/// describe the transformation for explaining the status line
+impl std::fmt::Display for StatusLineTransformation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RegexReplace(re) => write!(f, "replace {:?} with {:?}", re.0.as_str(), re.1),
+            Self::Capitalize => write!(f, "capitalize"),
+            Self::ChangeCase(from, to) => write!(f, "convert case from {:?} to {:?}", from, to),
+        }
+    }
+}
+
 /// prepare ops (regexes/replacements) to process the complete status line
 impl TryFrom<Replacement> for StatusLineTransformation {
     type Error = RahmenError;
@@ -133,6 +215,7 @@ impl TryFrom<Replacement> for StatusLineTransformation {
 struct StatusLineElement {
     tags: Vec<String>,
     transformations: Vec<StatusLineTransformation>,
+    gps_format: GpsFormat,
 }
 
 /// prepare the ops for the processing of an element
@@ -165,38 +248,140 @@ impl TryFrom<Element> for StatusLineElement {
         Ok(Self {
             transformations,
             tags: value.exif_tags,
+            gps_format: value.gps_format.unwrap_or_default(),
         })
     }
 }
 
 /// the status line meta data element
 impl StatusLineElement {
+    /// read a tag as string, either a virtual tag or from the metadata
+    fn read_tag(
+        &self,
+        metadata: &Metadata,
+        tag: &str,
+        geocoder: Option<&Geocoder>,
+    ) -> Option<String> {
+        if tags::is_virtual(tag) {
+            tags::read_virtual(metadata, tag, self.gps_format, geocoder)
+        } else {
+            metadata.get_tag_interpreted_string(tag).ok()
+        }
+    }
+
     /// this processes each metadata tag and subordinate instructions from the config file
-    fn process(&self, metadata: &Metadata) -> Option<String> {
-        // metadata processor: get the metadata value of the given meta tag (self.tag, from try_from above)
-        // so we have three values here, self.tag (the tag), metadata (the data for this tag),
-        // and value (the processed and later transformed metadata)
-        // If the current metadata tag (self.tag.iter) can be converted to some value...
-        if let Some(mut value) = self
+    fn process(&self, metadata: &Metadata, geocoder: Option<&Geocoder>) -> Option<String> {
+        // use the first tag that has a value, and transform it
+        self.tags
+            .iter()
+            .find_map(|tag| self.read_tag(metadata, tag, geocoder))
+            .map(|value| self.transform(value, |_, _| {}))
+    }
+
+    /// process the element like [`Self::process`], recording each step
+    fn explain(&self, metadata: &Metadata, geocoder: Option<&Geocoder>) -> ElementExplanation {
+        let tags: Vec<_> = self
             .tags
             .iter()
-            // ...get tag as string...
-            .map(|f| metadata.get_tag_interpreted_string(f).ok())
-            // ...if it is s/th,...
-            .find(Option::is_some)
-            .flatten()
-        // ...process that value using the pushed transformation ops and return the transformed value
-        {
-            for transformation in &self.transformations {
-                value = transformation.transform(value);
-            }
-            Some(value)
-        } else {
-            None
+            .map(|tag| (tag.clone(), self.read_tag(metadata, tag, geocoder)))
+            .collect();
+        let matched = tags
+            .iter()
+            .find_map(|(tag, value)| value.as_ref().map(|value| (tag.clone(), value.clone())));
+        let mut steps = vec![];
+        if let Some((_, value)) = matched.clone() {
+            self.transform(value, |transformation, value| {
+                steps.push((transformation.to_string(), value.to_string()));
+            });
+        }
+        ElementExplanation {
+            tags,
+            matched: matched.map(|(tag, _)| tag),
+            steps,
+        }
+    }
+
+    /// apply the transformations in order, passing each one and its output to `step`
+    fn transform(
+        &self,
+        mut value: String,
+        mut step: impl FnMut(&StatusLineTransformation, &str),
+    ) -> String {
+        for transformation in &self.transformations {
+            value = transformation.transform(value);
+            step(transformation, &value);
+        }
+        value
+    }
+}
+
+/// How a status line element was produced from the metadata of an image
+#[derive(Debug, Clone)]
+pub struct ElementExplanation {
+    /// Each configured tag with its value, `None` if the image doesn't have it
+    pub tags: Vec<(String, Option<String>)>,
+    /// The first tag with a value, which is used for the element
+    pub matched: Option<String>,
+    /// Each transformation step and its output
+    pub steps: Vec<(String, String)>,
+}
+
+impl ElementExplanation {
+    /// The value of the element after all transformations, empty if no tag matched
+    pub fn value(&self) -> String {
+        match (&self.matched, self.steps.last()) {
+            (_, Some((_, value))) => value.clone(),
+            (Some(tag), None) => self
+                .tags
+                .iter()
+                .find(|(t, _)| t == tag)
+                .and_then(|(_, value)| value.clone())
+                .unwrap_or_default(),
+            (None, None) => String::new(),
         }
     }
 }
 
+/// How the status line was produced from the metadata of an image, see
+/// [`StatusLineFormatter::explain`]
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// The explanation of each element
+    pub elements: Vec<ElementExplanation>,
+    /// The elements passed to the Python postprocessor, if configured
+    pub postprocess_input: Option<Vec<String>>,
+    /// The elements returned by the Python postprocessor, or the elements without one
+    pub elements_out: Vec<String>,
+    /// The final status line
+    pub line: String,
+}
+
+/// Prepend `paths` to the Python search path, skipping paths already present
+pub fn add_python_paths(paths: &[String]) -> RahmenResult<()> {
+    Python::attach(|py| -> PyResult<()> {
+        let syspath = py.import("sys")?.getattr("path")?.cast_into::<PyList>()?;
+        for path in paths {
+            if !syspath.contains(path)? {
+                syspath.insert(0, path)?;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Reload a Python module imported before, so changes to its file take effect
+pub fn reload_python_module(name: &str) -> RahmenResult<()> {
+    Python::attach(|py| -> PyResult<()> {
+        let modules = py.import("sys")?.getattr("modules")?;
+        if let Ok(module) = modules.get_item(name) {
+            py.import("importlib")?.call_method1("reload", (module,))?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
 /// A status line formatter formats meta data tags according to configured elements into a string
 /// and then processes that string using regexes/replacements as configured
 #[derive(Debug)]
@@ -207,6 +392,8 @@ pub struct StatusLineFormatter {
     separator: String,
     // the Python code used to postprocess the metadata items
     py_postprocess_fn: Option<Py<PyAny>>,
+    // the place names for the Rahmen.Geo tags
+    geocoder: Option<Arc<Geocoder>>,
 }
 
 impl StatusLineFormatter {
@@ -216,6 +403,7 @@ impl StatusLineFormatter {
         statusline_elements_iter: I,
         py_postprocess: Option<String>,
         separator: String,
+        geocoder: Option<Arc<Geocoder>>,
     ) -> RahmenResult<Self> {
         // read the metadata config entries and store them to the elements vector
         let mut elements = vec![];
@@ -236,6 +424,7 @@ impl StatusLineFormatter {
             elements,
             py_postprocess_fn,
             separator,
+            geocoder,
         })
     }
 
@@ -251,7 +440,7 @@ impl StatusLineFormatter {
             // being sure that parameters stay at their position.
             // This produces a Vec<String> of all the metadata found (empty strings if no data).
             .flat_map(move |element| {
-                if let Some(v) = element.process(&metadata) {
+                if let Some(v) = element.process(&metadata, self.geocoder.as_deref()) {
                     Some(v)
                 } else {
                     Some("".to_string())
@@ -259,29 +448,59 @@ impl StatusLineFormatter {
             })
             .collect();
 
-        // postprocess the status line using a python function defined in the config file (if it exists)
-        // this takes a Vec<String> of all the metadata found (empty strings if no data)
-        // and produces a Vec<String> of either the items returned from the Python code (if there's some code),
-        // or just the input
-        line_elements = if let Some(code) = &self.py_postprocess_fn {
+        line_elements = self.postprocess(line_elements)?;
+        Ok(self.join(&line_elements))
+    }
+
+    /// Format the meta data from the given path like [`Self::format`], and record how each
+    /// element was produced
+    pub fn explain<P: AsRef<std::ffi::OsStr>>(&self, path: P) -> RahmenResult<Explanation> {
+        let metadata = Metadata::new_from_path(path)?;
+        let elements: Vec<_> = self
+            .elements
+            .iter()
+            .map(|element| element.explain(&metadata, self.geocoder.as_deref()))
+            .collect();
+        let line_elements: Vec<_> = elements.iter().map(ElementExplanation::value).collect();
+        let postprocess_input = self
+            .py_postprocess_fn
+            .is_some()
+            .then(|| line_elements.clone());
+        let elements_out = self.postprocess(line_elements)?;
+        let line = self.join(&elements_out);
+        Ok(Explanation {
+            elements,
+            postprocess_input,
+            elements_out,
+            line,
+        })
+    }
+
+    /// postprocess the status line using a python function defined in the config file (if it exists)
+    /// this takes a Vec<String> of all the metadata found (empty strings if no data)
+    /// and produces a Vec<String> of either the items returned from the Python code (if there's some code),
+    /// or just the input
+    fn postprocess(&self, line_elements: Vec<String>) -> PyResult<Vec<String>> {
+        if let Some(code) = &self.py_postprocess_fn {
             Python::attach(|py| -> PyResult<Vec<String>> {
                 let tags = PyList::new(py, &line_elements)?;
                 code.bind(py)
                     .call1((tags, self.separator.as_str()))?
                     .extract()
             })
-            .unwrap()
         } else {
             // do nothing when there's no Python code
-            line_elements
-        };
+            Ok(line_elements)
+        }
+    }
 
-        // unconditionally filter out the empty items we received from above and
-        // deduplicate them, and join them with the separator, producing the final status line
-        Ok(line_elements
+    /// unconditionally filter out the empty items we received from above and
+    /// deduplicate them, and join them with the separator, producing the final status line
+    fn join(&self, line_elements: &[String]) -> String {
+        line_elements
             .iter()
             .filter(|x| !x.is_empty())
             .unique()
-            .join(&self.separator))
+            .join(&self.separator)
     }
 }
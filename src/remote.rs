@@ -0,0 +1,105 @@
+//! A REST API and a small web page to control the slideshow from a phone or browser.
+//!
+//! The API is served by the HTTP server of the [`crate::stream`] module. The commands of
+//! [`crate::control`] that move through the slideshow have a route: `POST /api/<command>` with the
+//! parameters of the command as JSON object in the body, e.g. `POST /api/set_delay` with
+//! `{"seconds": 30}`. `GET /api/status` answers status queries. The replies are the same JSON
+//! objects the control socket answers.
+//!
+//! The API has no authentication. POST requests must have the content type `application/json`,
+//! which browsers only send to other sites after a preflight request this server rejects, so web
+//! pages cannot send commands in the background.
+
+use std::io::Read;
+use std::sync::mpsc::Sender;
+
+use serde::Deserialize;
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::control::{self, Command, Reply};
+use crate::errors::RahmenResult;
+
+/// Maximum size of a request body
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+
+/// Commands that can be sent with `POST /api/<command>`
+const COMMANDS: &[&str] = &["next", "previous", "pause", "resume", "set_delay"];
+
+/// Page with buttons to control the slideshow, the current frame and its caption
+const REMOTE_PAGE: &str = include_str!("remote.html");
+
+/// Serve the remote control page at `/remote` and the API below `/api/`. Returns the request
+/// back if the path is not handled here.
+pub(crate) fn handle(
+    mut request: Request,
+    path: &str,
+    requests: &Sender<control::Request>,
+) -> RahmenResult<Option<Request>> {
+    let json = Header::from_bytes("Content-Type", "application/json").unwrap();
+    if path == "/remote" {
+        let html = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+        request.respond(Response::from_string(REMOTE_PAGE).with_header(html))?;
+        return Ok(None);
+    }
+    let Some(name) = path.strip_prefix("/api/") else {
+        return Ok(Some(request));
+    };
+    let reply = match (request.method(), name) {
+        (Method::Get, "status") => control::submit_command(Command::Status, requests),
+        (Method::Post, _) if !COMMANDS.contains(&name) => {
+            request.respond(Response::empty(404))?;
+            return Ok(None);
+        }
+        (Method::Post, _) if !is_json(&request) => {
+            request.respond(Response::empty(415))?;
+            return Ok(None);
+        }
+        (Method::Post, _) => match parse_command(name, &mut request) {
+            Ok(command) => control::submit_command(command, requests),
+            Err(err) => Reply::error(err),
+        },
+        _ => {
+            request.respond(Response::empty(405))?;
+            return Ok(None);
+        }
+    };
+    let status = if reply.ok { 200 } else { 400 };
+    let body = serde_json::to_string(&reply).map_err(std::io::Error::other)?;
+    request.respond(
+        Response::from_string(body)
+            .with_status_code(status)
+            .with_header(json),
+    )?;
+    Ok(None)
+}
+
+/// Whether the body of `request` is declared as JSON
+fn is_json(request: &Request) -> bool {
+    request.headers().iter().any(|header| {
+        header.field.equiv("Content-Type")
+            && header
+                .value
+                .as_str()
+                .split(';')
+                .next()
+                .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"))
+    })
+}
+
+/// Build the command `name` from the parameters in the body of `request`
+fn parse_command(name: &str, request: &mut Request) -> Result<Command, String> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_SIZE)
+        .read_to_string(&mut body)
+        .map_err(|err| err.to_string())?;
+    let mut parameters = if body.trim().is_empty() {
+        serde_json::Map::new()
+    } else {
+        serde_json::from_str(&body).map_err(|err| format!("Invalid parameters: {}", err))?
+    };
+    parameters.insert("command".into(), name.into());
+    Command::deserialize(serde_json::Value::Object(parameters))
+        .map_err(|err| format!("Invalid command: {}", err))
+}
@@ -0,0 +1,101 @@
+//! Daily schedules, evaluated against the local time of day
+
+use chrono::NaiveTime;
+use image::{DynamicImage, Rgb};
+
+use crate::config::{Dim, PowerOff};
+
+/// Determine whether `now` falls into the daily window from `from` to `to`. Windows with `to`
+/// before `from` span midnight.
+pub fn in_window(from: NaiveTime, to: NaiveTime, now: NaiveTime) -> bool {
+    if from <= to {
+        from <= now && now < to
+    } else {
+        now >= from || now < to
+    }
+}
+
+/// How to dim the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dimming {
+    /// Brightness factor from 0 to 1
+    pub brightness: f32,
+    /// Shift the colors to a warm color temperature
+    pub warm: bool,
+    /// Hide the image and only show the clock
+    pub clock_only: bool,
+}
+
+impl From<&Dim> for Dimming {
+    fn from(dim: &Dim) -> Self {
+        Self {
+            brightness: dim.brightness.unwrap_or(0.3).clamp(0., 1.),
+            warm: dim.warm.unwrap_or(false),
+            clock_only: dim.clock_only.unwrap_or(false),
+        }
+    }
+}
+
+impl Dimming {
+    /// Scale the pixel values of an image by the brightness and color temperature
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        // red, green and blue factors of a warm white, roughly 3000K
+        let tint = if self.warm { [1., 0.8, 0.55] } else { [1.; 3] };
+        let lut: [[u8; 256]; 3] = std::array::from_fn(|channel| {
+            std::array::from_fn(|value| (value as f32 * self.brightness * tint[channel]) as u8)
+        });
+        let mut rgb = img.to_rgb8();
+        for Rgb(pixel) in rgb.pixels_mut() {
+            for (channel, value) in pixel.iter_mut().enumerate() {
+                *value = lut[channel][*value as usize];
+            }
+        }
+        DynamicImage::ImageRgb8(rgb)
+    }
+}
+
+/// A schedule of daily time windows to dim the screen
+#[derive(Debug, Default, Clone)]
+pub struct DimSchedule {
+    entries: Vec<Dim>,
+}
+
+impl DimSchedule {
+    /// Create a new schedule from the configured time windows
+    pub fn new(entries: Vec<Dim>) -> Self {
+        Self { entries }
+    }
+
+    /// Returns true if any time window hides the image
+    pub fn has_clock_only(&self) -> bool {
+        self.entries.iter().any(|dim| dim.clock_only == Some(true))
+    }
+
+    /// The dimming at time of day `now`, taken from the first matching time window
+    pub fn dimming_at(&self, now: NaiveTime) -> Option<Dimming> {
+        self.entries
+            .iter()
+            .find(|dim| in_window(dim.from.0, dim.to.0, now))
+            .map(Dimming::from)
+    }
+}
+
+/// A schedule of daily time windows to power off the screen
+#[derive(Debug, Default, Clone)]
+pub struct PowerSchedule {
+    entries: Vec<PowerOff>,
+}
+
+impl PowerSchedule {
+    /// Create a new schedule from the configured time windows
+    pub fn new(entries: Vec<PowerOff>) -> Self {
+        Self { entries }
+    }
+
+    /// Returns true if the screen should be powered off at time of day `now`
+    pub fn is_off_at(&self, now: NaiveTime) -> bool {
+        self.entries
+            .iter()
+            .any(|power_off| in_window(power_off.from.0, power_off.to.0, now))
+    }
+}
@@ -0,0 +1,53 @@
+//! Control a running slideshow with Unix signals, e.g. from shell scripts.
+//!
+//! `SIGUSR1` shows the next image, `SIGUSR2` the previous one, `SIGHUP` reloads the configuration
+//! file, and `SIGTERM` or `SIGINT` quit. The signals are turned into [`crate::control`] commands.
+
+use std::sync::mpsc::Sender;
+
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGUSR1, SIGUSR2};
+use signal_hook::iterator::Signals;
+
+use crate::control::{self, Command};
+use crate::errors::RahmenResult;
+
+/// The command a signal stands for
+pub fn command_for(signal: i32) -> Option<Command> {
+    match signal {
+        SIGUSR1 => Some(Command::Next),
+        SIGUSR2 => Some(Command::Previous),
+        SIGHUP => Some(Command::ReloadConfig),
+        SIGTERM | SIGINT => Some(Command::Quit),
+        _ => None,
+    }
+}
+
+/// Handle the signals in a background thread, sending their commands to `requests`. A second
+/// `SIGTERM` or `SIGINT` exits immediately, calling `restore` first, in case the slideshow does not
+/// terminate.
+pub fn forward_signals(requests: Sender<control::Request>, restore: fn()) -> RahmenResult<()> {
+    let mut signals = Signals::new([SIGUSR1, SIGUSR2, SIGHUP, SIGTERM, SIGINT])?;
+    std::thread::spawn(move || {
+        let mut quitting = false;
+        for signal in signals.forever() {
+            let Some(command) = command_for(signal) else {
+                continue;
+            };
+            if command == Command::Quit {
+                if quitting {
+                    warn!("Exiting immediately");
+                    restore();
+                    std::process::exit(1);
+                }
+                quitting = true;
+            }
+            info!("Received signal {}", signal);
+            // do not wait for the reply, the slideshow may terminate before replying
+            let (request, _reply) = control::Request::new(command);
+            if requests.send(request).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,309 @@
+//! Serve the frame currently shown over HTTP, as a JPEG snapshot and as an MJPEG stream.
+//!
+//! A [`StreamDisplay`] wraps the actual display and mirrors everything rendered into a
+//! [`FrameStream`], which publishes the frame on every update. The frame is shared with the
+//! clients and only copied if it changes while a client still encodes it. The frames are only
+//! encoded when a client asks for them, and each frame is encoded at most once. At most
+//! [`MAX_CLIENTS`] clients are served at the same time, further requests are answered with
+//! "503 Service Unavailable".
+//!
+//! The same server can serve the REST API and the web remote of the [`crate::remote`] module.
+
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+
+use image::{DynamicImage, RgbaImage};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::Vector;
+use crate::control;
+use crate::display::{Display, ShadowBuffer};
+use crate::errors::RahmenResult;
+
+/// Default JPEG quality
+const DEFAULT_QUALITY: f32 = 80.;
+
+/// Maximum number of clients served at the same time, each from its own thread
+pub const MAX_CLIENTS: usize = 8;
+
+/// Boundary between the parts of the MJPEG stream
+const BOUNDARY: &str = "rahmenframe";
+
+/// Minimal page showing the stream
+const INDEX_PAGE: &str = "<!DOCTYPE html>\n<html><head><title>Rahmen</title>\
+    <meta name=\"viewport\" content=\"width=device-width\"></head>\
+    <body style=\"margin:0;background:#000\">\
+    <img src=\"stream.mjpg\" style=\"width:100%\" alt=\"Current frame\"></body></html>\n";
+
+#[derive(Debug, Default)]
+struct Frame {
+    /// Number of the frame, increasing with every update
+    sequence: u64,
+    /// The frame, unless it has changed since it was published
+    image: Weak<ShadowBuffer>,
+    /// Encoded image and the sequence number it belongs to
+    jpeg: Option<(u64, Arc<Vec<u8>>)>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    frame: Mutex<Frame>,
+    changed: Condvar,
+    quality: f32,
+    /// Number of clients currently served
+    clients: AtomicUsize,
+    /// Where to send the commands of the remote control, if enabled
+    requests: Option<Sender<control::Request>>,
+}
+
+impl Shared {
+    /// Wait for a frame newer than `sequence` and return it as JPEG
+    fn next_jpeg(&self, sequence: u64) -> RahmenResult<(u64, Arc<Vec<u8>>)> {
+        let (current, image) = {
+            let mut frame = self.frame.lock().unwrap();
+            loop {
+                if frame.sequence > sequence {
+                    if let Some((encoded, jpeg)) = &frame.jpeg {
+                        if *encoded == frame.sequence {
+                            return Ok((*encoded, Arc::clone(jpeg)));
+                        }
+                    }
+                    // keeps the frame from changing while it is encoded
+                    if let Some(image) = frame.image.upgrade() {
+                        break (frame.sequence, image);
+                    }
+                }
+                frame = self.changed.wait(frame).unwrap();
+            }
+        };
+        // encode without holding the lock, so the display is not blocked
+        let jpeg = Arc::new(encode_jpeg(image.image(), self.quality)?);
+        let mut frame = self.frame.lock().unwrap();
+        if frame
+            .jpeg
+            .as_ref()
+            .is_none_or(|(encoded, _)| *encoded < current)
+        {
+            frame.jpeg = Some((current, Arc::clone(&jpeg)));
+        }
+        Ok((current, jpeg))
+    }
+}
+
+/// A client counted in [`Shared::clients`] until it is dropped
+struct Client(Arc<Shared>);
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.0.clients.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Encode an image as baseline JPEG with mozjpeg
+pub fn encode_jpeg(image: &RgbaImage, quality: f32) -> RahmenResult<Vec<u8>> {
+    let _t = crate::Timer::new(|e| debug!("Encoding JPEG {}ms", e.as_millis()));
+    let mut compress = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_EXT_RGBA);
+    compress.set_size(image.width() as _, image.height() as _);
+    compress.set_quality(quality);
+    let mut started = compress.start_compress(Vec::new())?;
+    started.write_scanlines(image.as_raw())?;
+    Ok(started.finish()?)
+}
+
+/// The latest frame, served over HTTP
+#[derive(Debug)]
+pub struct FrameStream {
+    shared: Arc<Shared>,
+    image: Arc<ShadowBuffer>,
+    address: Option<SocketAddr>,
+}
+
+impl FrameStream {
+    /// Start an HTTP server listening on `listen` in a background thread. `quality` is the JPEG
+    /// quality from 1 to 100. With `requests`, the server also provides the remote control and
+    /// sends its commands there.
+    pub fn start(
+        listen: &str,
+        quality: Option<f32>,
+        requests: Option<Sender<control::Request>>,
+    ) -> RahmenResult<Self> {
+        let server = Server::http(listen).map_err(std::io::Error::other)?;
+        let address = server.server_addr().to_ip();
+        info!("Serving the current frame on http://{}/", listen);
+        let shared = Arc::new(Shared {
+            frame: Default::default(),
+            changed: Condvar::new(),
+            quality: quality.unwrap_or(DEFAULT_QUALITY).clamp(1., 100.),
+            clients: AtomicUsize::new(0),
+            requests,
+        });
+        let server_shared = Arc::clone(&shared);
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                if server_shared.clients.fetch_add(1, Ordering::SeqCst) >= MAX_CLIENTS {
+                    server_shared.clients.fetch_sub(1, Ordering::SeqCst);
+                    debug!("Rejecting HTTP client, serving {} already", MAX_CLIENTS);
+                    let _ = request.respond(Response::empty(503));
+                    continue;
+                }
+                let client = Client(Arc::clone(&server_shared));
+                // streams are long-lived, serve each client from its own thread
+                std::thread::spawn(move || {
+                    if let Err(err) = handle(request, &client.0) {
+                        debug!("HTTP client failed: {}", err);
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            shared,
+            image: Default::default(),
+            address,
+        })
+    }
+
+    /// The address the server listens on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.address
+    }
+
+    /// Publish the current frame to the clients, unless nothing was rendered yet
+    fn publish(&self) {
+        if self.image.dimensions() == (0, 0) {
+            return;
+        }
+        let mut frame = self.shared.frame.lock().unwrap();
+        frame.sequence += 1;
+        frame.image = Arc::downgrade(&self.image);
+        self.shared.changed.notify_all();
+    }
+
+    /// The frame to draw into. It is copied only if a client is encoding it, otherwise the
+    /// published frame is withdrawn until the next update.
+    fn image_mut(&mut self, dimensions: (u32, u32)) -> &mut ShadowBuffer {
+        let image = Arc::make_mut(&mut self.image);
+        image.match_dimensions(dimensions);
+        image
+    }
+}
+
+fn handle(request: Request, shared: &Shared) -> RahmenResult<()> {
+    // ignore query strings, e.g. to bypass caches
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let request = match &shared.requests {
+        Some(requests) => match crate::remote::handle(request, &path, requests)? {
+            Some(request) => request,
+            None => return Ok(()),
+        },
+        None => request,
+    };
+    if request.method() != &Method::Get {
+        return Ok(request.respond(Response::empty(405))?);
+    }
+    let header = |name: &str, value: &str| Header::from_bytes(name, value).unwrap();
+    match path.as_str() {
+        "/" => Ok(request.respond(
+            Response::from_string(INDEX_PAGE)
+                .with_header(header("Content-Type", "text/html; charset=utf-8")),
+        )?),
+        "/snapshot.jpg" => {
+            if shared.frame.lock().unwrap().sequence == 0 {
+                // nothing rendered yet
+                return Ok(request
+                    .respond(Response::empty(503).with_header(header("Retry-After", "1")))?);
+            }
+            let (_, jpeg) = shared.next_jpeg(0)?;
+            Ok(request.respond(
+                Response::from_data(jpeg.as_slice())
+                    .with_header(header("Content-Type", "image/jpeg"))
+                    .with_header(header("Cache-Control", "no-cache")),
+            )?)
+        }
+        "/stream.mjpg" => {
+            let mut writer = request.into_writer();
+            write!(
+                writer,
+                "HTTP/1.1 200 OK\r\n\
+                Content-Type: multipart/x-mixed-replace; boundary={}\r\n\
+                Cache-Control: no-cache\r\n\
+                Connection: close\r\n\r\n",
+                BOUNDARY
+            )?;
+            let mut sequence = 0;
+            loop {
+                let (current, jpeg) = shared.next_jpeg(sequence)?;
+                sequence = current;
+                write!(
+                    writer,
+                    "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+                    BOUNDARY,
+                    jpeg.len()
+                )?;
+                writer.write_all(&jpeg)?;
+                writer.write_all(b"\r\n")?;
+                writer.flush()?;
+            }
+        }
+        _ => Ok(request.respond(Response::empty(404))?),
+    }
+}
+
+/// A display mirroring everything rendered on another display into a [`FrameStream`]
+#[derive(Debug)]
+pub struct StreamDisplay<'a, D: Display + ?Sized> {
+    display: &'a mut D,
+    stream: &'a mut FrameStream,
+}
+
+impl<'a, D: Display + ?Sized> StreamDisplay<'a, D> {
+    /// Wrap `display`, publishing its frames to `stream`
+    pub fn new(display: &'a mut D, stream: &'a mut FrameStream) -> Self {
+        Self { display, stream }
+    }
+}
+
+impl<D: Display + ?Sized> Display for StreamDisplay<'_, D> {
+    fn render(&mut self, key: usize, anchor: Vector, img: &DynamicImage) -> RahmenResult<()> {
+        self.display.render(key, anchor, img)?;
+        self.stream
+            .image_mut(self.display.dimensions())
+            .render(anchor, &img.to_rgba8())
+    }
+
+    fn blank(&mut self, key: usize, anchor: Vector, size: Vector) -> RahmenResult<()> {
+        self.display.blank(key, anchor, size)?;
+        self.stream
+            .image_mut(self.display.dimensions())
+            .blank(anchor, size)
+    }
+
+    fn update(&mut self) -> RahmenResult<()> {
+        self.display.update()?;
+        self.stream.publish();
+        Ok(())
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.display.dimensions()
+    }
+
+    fn power_off(&mut self) -> RahmenResult<()> {
+        self.display.power_off()
+    }
+
+    fn power_on(&mut self) -> RahmenResult<()> {
+        self.display.power_on()
+    }
+
+    fn pressed_keys(&mut self) -> Vec<String> {
+        self.display.pressed_keys()
+    }
+}
@@ -0,0 +1,89 @@
+//! Virtual tags computed from the metadata, usable in `exif_tags` like the exiv2 keys.
+//!
+//! The `Rahmen.GPS` tags show the GPS position of an image, formatted according to the
+//! `gps_format` of the status line element. The `Rahmen.Geo` tags show the place nearest to the
+//! position, looked up with the [`Geocoder`].
+
+use crate::config::GpsFormat;
+use crate::exiv2::Metadata;
+use crate::geocode::Geocoder;
+
+/// The virtual tags, in the order `rahmen tags` lists them
+pub const VIRTUAL_TAGS: &[&str] = &[
+    "Rahmen.GPS.Position",
+    "Rahmen.GPS.Latitude",
+    "Rahmen.GPS.Longitude",
+    "Rahmen.GPS.Altitude",
+    "Rahmen.GPS.Time",
+    "Rahmen.Geo.City",
+    "Rahmen.Geo.Region",
+    "Rahmen.Geo.Country",
+    "Rahmen.Geo.CountryCode",
+];
+
+/// Whether `key` names a virtual tag which needs a [`Geocoder`]
+pub fn needs_geocoder(key: &str) -> bool {
+    is_virtual(key) && key.starts_with("Rahmen.Geo.")
+}
+
+/// Whether `key` names a virtual tag
+pub fn is_virtual(key: &str) -> bool {
+    VIRTUAL_TAGS.contains(&key)
+}
+
+/// Format a coordinate, using `positive` or `negative` as the hemisphere for degrees, minutes and
+/// seconds, e.g. `33°26'52.8"S`
+pub fn format_coordinate(value: f64, positive: char, negative: char, format: GpsFormat) -> String {
+    match format {
+        GpsFormat::Decimal => format!("{:.5}", value),
+        GpsFormat::Dms => {
+            // round to tenths of arc seconds first, so 59.96 seconds become a full minute
+            let tenths = (value.abs() * 36000.).round() as u64;
+            format!(
+                "{}°{}'{}.{}\"{}",
+                tenths / 36000,
+                tenths % 36000 / 600,
+                tenths % 600 / 10,
+                tenths % 10,
+                if value < 0. { negative } else { positive }
+            )
+        }
+    }
+}
+
+/// Read the virtual tag `key`, `None` if it is unknown or the image lacks the data. The
+/// `Rahmen.Geo` tags are `None` without a `geocoder`.
+pub fn read_virtual(
+    metadata: &Metadata,
+    key: &str,
+    gps_format: GpsFormat,
+    geocoder: Option<&Geocoder>,
+) -> Option<String> {
+    let gps = metadata.gps()?;
+    if needs_geocoder(key) {
+        let place = geocoder?.nearest(gps.latitude, gps.longitude)?;
+        return match key {
+            "Rahmen.Geo.City" => Some(place.city),
+            "Rahmen.Geo.Region" => Some(place.region),
+            "Rahmen.Geo.Country" => Some(place.country),
+            "Rahmen.Geo.CountryCode" => Some(place.country_code),
+            _ => None,
+        }
+        .map(str::to_string);
+    }
+    let latitude = || format_coordinate(gps.latitude, 'N', 'S', gps_format);
+    let longitude = || format_coordinate(gps.longitude, 'E', 'W', gps_format);
+    match key {
+        "Rahmen.GPS.Position" => Some(match gps_format {
+            GpsFormat::Decimal => format!("{}, {}", latitude(), longitude()),
+            GpsFormat::Dms => format!("{} {}", latitude(), longitude()),
+        }),
+        "Rahmen.GPS.Latitude" => Some(latitude()),
+        "Rahmen.GPS.Longitude" => Some(longitude()),
+        "Rahmen.GPS.Altitude" => gps.altitude.map(|altitude| format!("{:.0} m", altitude)),
+        "Rahmen.GPS.Time" => gps
+            .timestamp
+            .map(|timestamp| timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+        _ => None,
+    }
+}
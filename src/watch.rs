@@ -0,0 +1,40 @@
+//! Watch the configuration file and reload it when it changes.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, SystemTime};
+
+use crate::control::{self, Command};
+
+/// How often to check the file for changes by default
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Modification time and size of a file, if it exists
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Check `path` for changes every `interval` in a background thread, and send a command to reload
+/// the configuration to `requests` on every change. Polling also notices files replaced by editors
+/// and files on network shares.
+pub fn watch_config(path: PathBuf, interval: Duration, requests: Sender<control::Request>) {
+    info!("Watching {:?} for changes", path);
+    std::thread::spawn(move || {
+        let mut last = stamp(&path);
+        loop {
+            std::thread::sleep(interval);
+            let current = stamp(&path);
+            // ignore the file disappearing while it is replaced
+            if current.is_none() || current == last {
+                continue;
+            }
+            last = current;
+            info!("{:?} changed", path);
+            let (request, _reply) = control::Request::new(Command::ReloadConfig);
+            if requests.send(request).is_err() {
+                return;
+            }
+        }
+    });
+}
@@ -0,0 +1,70 @@
+//! Tests explaining the status line of the `rahmen-exiv2` test fixture.
+
+use rahmen::config::{Element, Replacement};
+use rahmen::provider::StatusLineFormatter;
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/rahmen-exiv2/testdata/tagged.jpg"
+);
+
+fn formatter() -> StatusLineFormatter {
+    let elements = vec![
+        Element {
+            capitalize: None,
+            exif_tags: vec![
+                "Iptc.Application2.CountryName".into(),
+                "Iptc.Application2.City".into(),
+            ],
+            replace: Some(vec![Replacement {
+                regex: "u".into(),
+                replace: "ü".into(),
+            }]),
+            case_conversion: None,
+            gps_format: None,
+        },
+        Element {
+            capitalize: None,
+            exif_tags: vec!["Iptc.Application2.SubLocation".into()],
+            replace: None,
+            case_conversion: None,
+            gps_format: None,
+        },
+        Element {
+            capitalize: None,
+            exif_tags: vec!["Xmp.dc.creator".into()],
+            replace: None,
+            case_conversion: None,
+            gps_format: None,
+        },
+    ];
+    StatusLineFormatter::new(elements.into_iter(), None, ", ".into(), None).unwrap()
+}
+
+#[test]
+fn explains_each_step() {
+    let formatter = formatter();
+    let explanation = formatter.explain(FIXTURE).unwrap();
+    assert_eq!(explanation.line, formatter.format(FIXTURE).unwrap());
+    assert_eq!(explanation.line, "Zürich, Moritz Hoffmann");
+
+    let city = &explanation.elements[0];
+    assert_eq!(city.tags[0], ("Iptc.Application2.CountryName".into(), None));
+    assert_eq!(
+        city.tags[1],
+        ("Iptc.Application2.City".into(), Some("Zurich".into()))
+    );
+    assert_eq!(city.matched.as_deref(), Some("Iptc.Application2.City"));
+    assert_eq!(city.steps.len(), 1);
+    assert_eq!(city.steps[0].1, "Zürich");
+
+    assert_eq!(explanation.elements[1].matched, None);
+    assert!(explanation.elements[2].steps.is_empty());
+    assert!(explanation.postprocess_input.is_none());
+    assert_eq!(explanation.elements_out, ["Zürich", "", "Moritz Hoffmann"]);
+}
+
+#[test]
+fn missing_file_is_error() {
+    assert!(formatter().explain("/nonexistent/image.jpg").is_err());
+}
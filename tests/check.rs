@@ -0,0 +1,113 @@
+//! Tests checking the configuration.
+
+mod common;
+
+use common::temp_path;
+use rahmen::check::{check_settings, is_valid_key, read_settings};
+use rahmen::config::Settings;
+
+fn write_config(name: &str, content: &str) -> std::path::PathBuf {
+    let path = temp_path(&format!("{}.toml", name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn valid_keys() {
+    assert!(is_valid_key("Exif.Photo.DateTimeOriginal"));
+    assert!(is_valid_key("Iptc.Application2.City"));
+    assert!(is_valid_key("Xmp.dc.subject"));
+    assert!(is_valid_key("Rahmen.GPS.Position"));
+    assert!(!is_valid_key("Rahmen.GPS.Nowhere"));
+    assert!(!is_valid_key("Exif.Photo"));
+    assert!(!is_valid_key("Exif..DateTimeOriginal"));
+    assert!(!is_valid_key("Photo.DateTimeOriginal"));
+    assert!(!is_valid_key("exif.Photo.DateTimeOriginal"));
+}
+
+#[test]
+fn reports_all_problems() {
+    let path = write_config(
+        "check",
+        r#"
+delay = 10
+dleay = 20
+
+[[status_line]]
+exif_tags = ["Iptc.Application2.City", "Exif.Photo"]
+replace = [{ regex = "(unclosed", replace = "" }]
+case_conversion = { from = "lower", to = "shouting" }
+
+[[status_line]]
+exif_tags = []
+"#,
+    );
+    let (settings, unknown) = read_settings(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(unknown, ["dleay"]);
+    assert_eq!(settings.delay, Some(10.));
+
+    let problems = check_settings(&settings);
+    assert_eq!(problems.len(), 4, "{:?}", problems);
+    assert!(problems[0].contains("\"Exif.Photo\""));
+    assert!(problems[1].contains("regex"));
+    assert!(problems[2].contains("shouting"));
+    assert_eq!(problems[3], "status_line[1]: no exif_tags");
+}
+
+#[test]
+fn dumped_settings_read_back() {
+    let path = write_config(
+        "dump",
+        r##"
+delay = 10
+rotate = 90
+mat = { color = "#102030", width = 40 }
+
+[[status_line]]
+exif_tags = ["Iptc.Application2.City"]
+
+[[dim]]
+from = "22:00"
+to = "06:30:15"
+brightness = 0.2
+"##,
+    );
+    let (settings, _) = read_settings(&path).unwrap();
+    std::fs::write(&path, toml::to_string(&settings).unwrap()).unwrap();
+    let (dumped, unknown): (Settings, _) = read_settings(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(unknown.is_empty());
+    assert_eq!(
+        toml::to_string(&settings).unwrap(),
+        toml::to_string(&dumped).unwrap()
+    );
+    assert!(check_settings(&dumped).is_empty());
+}
+
+#[test]
+fn geo_tags_need_geonames() {
+    let path = write_config(
+        "geonames",
+        r#"
+[[status_line]]
+exif_tags = ["Rahmen.Geo.City", "Iptc.Application2.City"]
+"#,
+    );
+    let (mut settings, _) = read_settings(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        check_settings(&settings),
+        ["status_line[0]: \"Rahmen.Geo.City\" needs [geonames]"]
+    );
+
+    settings.geonames = Some(rahmen::config::GeoNames {
+        cities: "/nonexistent/cities15000.txt".into(),
+        admin1: None,
+        countries: None,
+        max_distance: None,
+    });
+    let problems = check_settings(&settings);
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("geonames: /nonexistent/cities15000.txt"));
+}
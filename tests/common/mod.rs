@@ -0,0 +1,12 @@
+//! Helpers shared by the tests.
+
+use std::path::PathBuf;
+
+/// A path in the temporary directory, unique to `name` and this process. Anything left there by
+/// an earlier run is removed.
+pub fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rahmen-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    path
+}
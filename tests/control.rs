@@ -0,0 +1,98 @@
+//! Tests the command protocol and the control socket.
+
+mod common;
+
+use std::sync::mpsc::channel;
+
+use rahmen::control::{Command, Reply, Status, submit};
+
+#[test]
+fn parses_commands() {
+    let parse = |line: &str| serde_json::from_str::<Command>(line).unwrap();
+    assert_eq!(parse(r#"{"command": "next"}"#), Command::Next);
+    assert_eq!(
+        parse(r#"{"command": "reload_config"}"#),
+        Command::ReloadConfig
+    );
+    assert_eq!(
+        parse(r#"{"command": "goto", "path": "a.jpg"}"#),
+        Command::Goto {
+            path: "a.jpg".into()
+        }
+    );
+    assert_eq!(
+        parse(r#"{"command": "set_delay", "seconds": 30}"#),
+        Command::SetDelay { seconds: 30. }
+    );
+    assert!(serde_json::from_str::<Command>(r#"{"command": "jump"}"#).is_err());
+}
+
+#[test]
+fn serializes_replies() {
+    assert_eq!(
+        serde_json::to_string(&Reply::ok()).unwrap(),
+        r#"{"ok":true}"#
+    );
+    assert_eq!(
+        serde_json::to_string(&Reply::error("bad")).unwrap(),
+        r#"{"ok":false,"error":"bad"}"#
+    );
+    let status = Reply::status(Status {
+        path: Some("a.jpg".into()),
+        index: 3,
+        caption: None,
+        remaining: 1.5,
+        paused: false,
+        powered: true,
+        brightness: 1.,
+    });
+    assert_eq!(
+        serde_json::to_string(&status).unwrap(),
+        r#"{"ok":true,"path":"a.jpg","index":3,"caption":null,"remaining":1.5,"paused":false,"powered":true,"brightness":1.0}"#
+    );
+}
+
+#[test]
+fn rejects_invalid_commands() {
+    let (sender, _requests) = channel();
+    let reply = submit("next", &sender);
+    assert!(!reply.ok);
+}
+
+#[cfg(unix)]
+#[test]
+fn answers_on_the_socket() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixStream;
+
+    use rahmen::control::ControlSocket;
+
+    let path = common::temp_path("control.sock");
+    let (sender, requests) = channel();
+    let socket = ControlSocket::bind(&path, sender).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    std::thread::spawn(move || {
+        for request in requests {
+            let reply = match request.command {
+                Command::Pause => Reply::ok(),
+                _ => Reply::error("unexpected"),
+            };
+            request.reply(reply);
+        }
+    });
+
+    let mut connection = UnixStream::connect(&path).unwrap();
+    connection
+        .write_all(b"{\"command\": \"pause\"}\n{\"command\": \"next\"}\n")
+        .unwrap();
+    let mut lines = BufReader::new(connection).lines();
+    assert_eq!(lines.next().unwrap().unwrap(), r#"{"ok":true}"#);
+    assert_eq!(
+        lines.next().unwrap().unwrap(),
+        r#"{"ok":false,"error":"unexpected"}"#
+    );
+    drop(socket);
+    assert!(!path.exists());
+}
@@ -0,0 +1,56 @@
+//! Tests tracking the rectangles changed on the framebuffer display.
+
+use rahmen::Vector;
+use rahmen::display_framebuffer::{Damage, MAX_DAMAGE};
+
+const SCREEN: (u32, u32) = (100, 50);
+
+#[test]
+fn clips_to_image() {
+    let mut damage = Damage::default();
+    damage.add(Vector::new(-10, 40), (30, 30), SCREEN);
+    damage.add(Vector::new(90, -5), (20, 10), SCREEN);
+    assert_eq!(damage.rects(), [(0, 40, 20, 10), (90, 0, 10, 5)]);
+}
+
+#[test]
+fn skips_empty_and_outside() {
+    let mut damage = Damage::default();
+    damage.add(Vector::new(10, 10), (0, 5), SCREEN);
+    damage.add(Vector::new(100, 10), (5, 5), SCREEN);
+    damage.add(Vector::new(-20, 0), (10, 10), SCREEN);
+    assert!(damage.rects().is_empty());
+}
+
+#[test]
+fn skips_contained_rects() {
+    let mut damage = Damage::default();
+    damage.add(Vector::new(10, 10), (50, 20), SCREEN);
+    damage.add(Vector::new(20, 15), (10, 10), SCREEN);
+    damage.add(Vector::new(10, 10), (50, 20), SCREEN);
+    assert_eq!(damage.rects(), [(10, 10, 50, 20)]);
+    // a larger rect does not replace the smaller one
+    damage.add(Vector::new(0, 0), (70, 40), SCREEN);
+    assert_eq!(damage.rects().len(), 2);
+}
+
+#[test]
+fn merges_beyond_max_damage() {
+    let mut damage = Damage::default();
+    for i in 0..MAX_DAMAGE as i32 {
+        damage.add(Vector::new(i * 5, i), (2, 2), SCREEN);
+    }
+    assert_eq!(damage.rects().len(), MAX_DAMAGE);
+    damage.add(Vector::new(90, 40), (5, 5), SCREEN);
+    assert_eq!(damage.rects(), [(0, 0, 95, 45)]);
+}
+
+#[test]
+fn take_clears() {
+    let mut damage = Damage::default();
+    damage.add(Vector::new(1, 2), (3, 4), SCREEN);
+    assert_eq!(damage.take(), [(1, 2, 3, 4)]);
+    assert!(damage.rects().is_empty());
+    damage.add_all(SCREEN);
+    assert_eq!(damage.rects(), [(0, 0, 100, 50)]);
+}
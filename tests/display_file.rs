@@ -0,0 +1,53 @@
+//! Tests rendering into PNG files with the headless file display.
+
+mod common;
+
+use common::temp_path;
+use image::{DynamicImage, Rgba, RgbaImage};
+use rahmen::Vector;
+use rahmen::display::Display;
+use rahmen::display_file::{CURRENT_FILE_NAME, FileDisplay};
+
+/// Render a red square with a blanked stripe and compare against the expected picture
+fn render_golden(display: &mut FileDisplay) -> RgbaImage {
+    let red = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+    display
+        .render(0, Vector::new(2, 1), &DynamicImage::ImageRgba8(red))
+        .unwrap();
+    display
+        .blank(0, Vector::new(2, 2), Vector::new(4, 1))
+        .unwrap();
+    display.update().unwrap();
+    RgbaImage::from_fn(8, 6, |x, y| {
+        if (2..6).contains(&x) && (1..5).contains(&y) && y != 2 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    })
+}
+
+#[test]
+fn writes_current_frame() {
+    let dir = temp_path("file-current");
+    let mut display = FileDisplay::new(&dir, (8, 6), false).unwrap();
+    assert_eq!(display.dimensions(), (8, 6));
+    let expected = render_golden(&mut display);
+    let written = image::open(dir.join(CURRENT_FILE_NAME)).unwrap().to_rgba8();
+    assert_eq!(written, expected);
+    assert_eq!(display.image(), &expected);
+}
+
+#[test]
+fn writes_numbered_frames() {
+    let dir = temp_path("file-sequence");
+    let mut display = FileDisplay::new(&dir, (8, 6), true).unwrap();
+    let expected = render_golden(&mut display);
+    display.update().unwrap();
+    let first = image::open(dir.join("frame-000001.png"))
+        .unwrap()
+        .to_rgba8();
+    assert_eq!(first, expected);
+    assert!(dir.join("frame-000002.png").exists());
+    assert!(!dir.join(CURRENT_FILE_NAME).exists());
+}
@@ -0,0 +1,88 @@
+//! Tests mirroring a display on displays of the same and of different dimensions.
+
+mod common;
+
+use common::temp_path;
+use image::{DynamicImage, Rgba, RgbaImage};
+use rahmen::Vector;
+use rahmen::display::Display;
+use rahmen::display_file::{CURRENT_FILE_NAME, FileDisplay};
+use rahmen::display_tee::{Mirrors, TeeDisplay};
+use rahmen::errors::{RahmenError, RahmenResult};
+use std::path::Path;
+
+fn written(dir: &Path) -> RgbaImage {
+    image::open(dir.join(CURRENT_FILE_NAME)).unwrap().to_rgba8()
+}
+
+#[test]
+fn mirrors_frames() {
+    let (main_dir, same_dir, larger_dir) = (
+        temp_path("tee-main"),
+        temp_path("tee-same"),
+        temp_path("tee-larger"),
+    );
+    let mut display = FileDisplay::new(&main_dir, (8, 4), false).unwrap();
+    let mut mirrors = Mirrors::new(vec![
+        Box::new(FileDisplay::new(&same_dir, (8, 4), false).unwrap()),
+        Box::new(FileDisplay::new(&larger_dir, (16, 16), false).unwrap()),
+    ]);
+    let red = RgbaImage::from_pixel(8, 4, Rgba([255, 0, 0, 255]));
+    {
+        let mut tee = TeeDisplay::new(&mut display, &mut mirrors);
+        assert_eq!(tee.dimensions(), (8, 4));
+        tee.render(0, Vector::zero(), &DynamicImage::ImageRgba8(red.clone()))
+            .unwrap();
+        tee.blank(0, Vector::new(0, 0), Vector::new(4, 4)).unwrap();
+        tee.update().unwrap();
+    }
+    let main = written(&main_dir);
+    assert_eq!(written(&same_dir), main);
+    assert_eq!(main.get_pixel(1, 1), &Rgba([0, 0, 0, 255]));
+    assert_eq!(main.get_pixel(6, 1), &Rgba([255, 0, 0, 255]));
+
+    // the larger mirror shows the frame scaled to 16x8, centered vertically
+    let larger = written(&larger_dir);
+    assert_eq!(larger.dimensions(), (16, 16));
+    assert_eq!(larger.get_pixel(12, 1), &Rgba([0, 0, 0, 255]));
+    assert_eq!(larger.get_pixel(2, 8), &Rgba([0, 0, 0, 255]));
+    assert_eq!(larger.get_pixel(14, 8), &Rgba([255, 0, 0, 255]));
+    assert_eq!(larger.get_pixel(14, 14), &Rgba([0, 0, 0, 255]));
+}
+
+/// A mirror whose output is gone
+struct Unplugged;
+
+impl Display for Unplugged {
+    fn render(&mut self, _key: usize, _anchor: Vector, _img: &DynamicImage) -> RahmenResult<()> {
+        Err(RahmenError::WindowError("unplugged".into()))
+    }
+
+    fn blank(&mut self, _key: usize, _anchor: Vector, _size: Vector) -> RahmenResult<()> {
+        Err(RahmenError::WindowError("unplugged".into()))
+    }
+
+    fn update(&mut self) -> RahmenResult<()> {
+        Err(RahmenError::WindowError("unplugged".into()))
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        (8, 4)
+    }
+}
+
+#[test]
+fn disables_failing_mirrors() {
+    let main_dir = temp_path("tee-failing");
+    let mut display = FileDisplay::new(&main_dir, (8, 4), false).unwrap();
+    let mut mirrors = Mirrors::new(vec![Box::new(Unplugged)]);
+    let red = RgbaImage::from_pixel(8, 4, Rgba([255, 0, 0, 255]));
+    {
+        let mut tee = TeeDisplay::new(&mut display, &mut mirrors);
+        tee.render(0, Vector::zero(), &DynamicImage::ImageRgba8(red.clone()))
+            .unwrap();
+        tee.update().unwrap();
+    }
+    assert!(mirrors.is_empty());
+    assert_eq!(written(&main_dir), red);
+}
@@ -0,0 +1,37 @@
+//! Tests encoding images for the terminal display.
+#![cfg(feature = "terminal")]
+
+use image::{Rgba, RgbaImage};
+use rahmen::display_terminal::{encode_blocks, encode_sixel};
+
+#[test]
+fn encodes_half_blocks() {
+    let image = RgbaImage::from_fn(1, 2, |_, y| {
+        if y == 0 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 255, 255])
+        }
+    });
+    assert_eq!(
+        encode_blocks(&image),
+        "\x1b[38;2;255;0;0;48;2;0;0;255m\u{2580}\x1b[0m"
+    );
+}
+
+#[test]
+fn encodes_sixel() {
+    // a red column over a blue column, six pixels high
+    let image = RgbaImage::from_fn(5, 6, |_, y| {
+        if y < 3 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 255, 255])
+        }
+    });
+    let sixel = encode_sixel(&image);
+    assert!(sixel.starts_with("\x1bPq\"1;1;5;6#0;2;0;0;0"));
+    // blue in the lower three rows (bits 0b111000), then red in the upper three (0b000111)
+    assert!(sixel.contains("#5!5w$#180!5F-"));
+    assert!(sixel.ends_with("\x1b\\"));
+}
@@ -0,0 +1,91 @@
+//! Tests for the offline reverse geocoding.
+
+use rahmen::config::GeoNames;
+use rahmen::geocode::{Geocoder, distance};
+
+/// A line of a GeoNames cities file
+fn city(name: &str, latitude: f64, longitude: f64, country: &str, admin1: &str) -> String {
+    format!(
+        "1\t{name}\t{name}\t\t{latitude}\t{longitude}\tP\tPPL\t{country}\t\t{admin1}\t\t\t\t1000\t\t500\tUTC\t2026-01-01\n"
+    )
+}
+
+fn cities() -> String {
+    [
+        city("Santiago", -33.45694, -70.64827, "CL", "12"),
+        city("Valparaíso", -33.03932, -71.62725, "CL", "01"),
+        city("Zürich", 47.36667, 8.55, "CH", "25"),
+        city("Taveuni", -16.85, 179.95, "FJ", "03"),
+    ]
+    .concat()
+}
+
+const ADMIN1: &str = "CL.12\tSantiago Metropolitan\tSantiago Metropolitan\t3873544\n\
+                      CH.25\tZurich\tZurich\t2657895\n";
+
+const COUNTRIES: &str = "# ISO\tISO3\tISO-Numeric\tfips\tCountry\n\
+                         CL\tCHL\t152\tCI\tChile\n\
+                         CH\tCHE\t756\tSZ\tSwitzerland\n";
+
+fn geocoder(max_distance: f64) -> Geocoder {
+    Geocoder::from_readers(
+        cities().as_bytes(),
+        Some(ADMIN1.as_bytes()),
+        Some(COUNTRIES.as_bytes()),
+        max_distance,
+    )
+    .unwrap()
+}
+
+#[test]
+fn measures_distance() {
+    // Santiago to Valparaíso is about 100 km
+    let d = distance(-33.45694, -70.64827, -33.03932, -71.62725);
+    assert!((d - 100.).abs() < 5., "{}", d);
+    assert_eq!(distance(47., 8., 47., 8.), 0.);
+}
+
+#[test]
+fn finds_nearest_city() {
+    let geocoder = geocoder(50.);
+    assert_eq!(geocoder.len(), 4);
+    let place = geocoder.nearest(-33.448, -70.67).unwrap();
+    assert_eq!(place.city, "Santiago");
+    assert_eq!(place.region, "Santiago Metropolitan");
+    assert_eq!(place.country, "Chile");
+    assert_eq!(place.country_code, "CL");
+    assert!(place.distance < 3.);
+
+    // without a region name, the code is used
+    assert_eq!(geocoder.nearest(-33.04, -71.6).unwrap().region, "01");
+    // across the antimeridian
+    assert_eq!(geocoder.nearest(-16.85, -179.95).unwrap().city, "Taveuni");
+    // nothing within the maximum distance
+    assert!(geocoder.nearest(0., 0.).is_none());
+    assert!(geocoder.nearest(90., 0.).is_none());
+}
+
+#[test]
+fn uses_codes_without_names() {
+    let geocoder =
+        Geocoder::from_readers(cities().as_bytes(), None::<&[u8]>, None::<&[u8]>, 50.).unwrap();
+    let place = geocoder.nearest(47.37, 8.54).unwrap();
+    assert_eq!(place.city, "Zürich");
+    assert_eq!(place.region, "25");
+    assert_eq!(place.country, "CH");
+}
+
+#[test]
+fn rejects_invalid_files() {
+    let invalid = "1\tSantiago\tSantiago\t\tsouth\twest\tP\tPPL\tCL\t\t12\n";
+    assert!(Geocoder::from_readers(invalid.as_bytes(), None::<&[u8]>, None::<&[u8]>, 50.).is_err());
+    assert!(
+        Geocoder::open(&GeoNames {
+            cities: "/nonexistent/cities15000.txt".into(),
+            admin1: None,
+            countries: None,
+            max_distance: None,
+        })
+        .is_err()
+    );
+}
@@ -0,0 +1,55 @@
+//! Tests going back and forth in the history of a provider.
+
+use rahmen::errors::RahmenResult;
+use rahmen::provider::{History, Provider};
+
+/// Provides the numbers up to a limit
+struct Count(u32, u32);
+
+impl Provider<u32> for Count {
+    fn next_image(&mut self) -> RahmenResult<Option<u32>> {
+        if self.0 < self.1 {
+            self.0 += 1;
+            Ok(Some(self.0))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[test]
+fn goes_back_and_forth() {
+    let mut history = History::new(Count(0, 3));
+    assert_eq!(history.previous_image(), None);
+    assert_eq!(history.next_image().unwrap(), Some(1));
+    assert_eq!(history.next_image().unwrap(), Some(2));
+    assert_eq!(history.previous_image(), Some(1));
+    assert_eq!(history.previous_image(), Some(1));
+    assert_eq!(history.next_image().unwrap(), Some(2));
+    assert_eq!(history.next_image().unwrap(), Some(3));
+    assert_eq!(history.next_image().unwrap(), None);
+}
+
+#[test]
+fn forgets_old_elements() {
+    let mut history = History::new(Count(0, 1000));
+    for _ in 0..150 {
+        history.next_image().unwrap();
+    }
+    for _ in 0..150 {
+        history.previous_image();
+    }
+    assert_eq!(history.previous_image(), Some(51));
+}
+
+#[test]
+fn goes_to_elements_out_of_order() {
+    let mut history = History::new(Count(0, 3));
+    assert_eq!(history.next_image().unwrap(), Some(1));
+    assert_eq!(history.goto(7), 7);
+    assert_eq!(history.index(), 1);
+    assert_eq!(history.previous_image(), Some(1));
+    assert_eq!(history.next_image().unwrap(), Some(7));
+    assert_eq!(history.next_image().unwrap(), Some(2));
+    assert_eq!(history.index(), 2);
+}
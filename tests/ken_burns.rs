@@ -0,0 +1,104 @@
+//! Tests of the Ken Burns geometry.
+
+use rahmen::ken_burns::{Pan, View, fit};
+
+const FULL: View = View {
+    x: 0.5,
+    y: 0.5,
+    scale: 1.,
+};
+
+#[test]
+fn full_view_covers_image() {
+    assert_eq!(FULL.to_rect((1000, 600)), (0, 0, 1000, 600));
+}
+
+#[test]
+fn view_is_centered() {
+    let view = View {
+        x: 0.5,
+        y: 0.5,
+        scale: 0.5,
+    };
+    assert_eq!(view.to_rect((1000, 600)), (250, 150, 500, 300));
+}
+
+#[test]
+fn view_stays_inside_image() {
+    let corner = View {
+        x: 0.,
+        y: 1.,
+        scale: 0.5,
+    };
+    assert_eq!(corner.to_rect((1000, 600)), (0, 300, 500, 300));
+    let tiny = View {
+        x: 1.,
+        y: 0.,
+        scale: 0.,
+    };
+    assert_eq!(tiny.to_rect((1000, 600)), (999, 0, 1, 1));
+}
+
+#[test]
+fn focal_point_zooms_in() {
+    let pan = Pan::choose(Some((0.25, 0.75)), 2.);
+    assert_eq!(pan.start, FULL);
+    assert_eq!(
+        pan.end,
+        View {
+            x: 0.25,
+            y: 0.75,
+            scale: 0.5,
+        }
+    );
+}
+
+#[test]
+fn random_pan_within_zoom() {
+    for _ in 0..100 {
+        let pan = Pan::choose(None, 1.5);
+        for view in [pan.start, pan.end] {
+            assert!((0. ..=1.).contains(&view.x));
+            assert!((0. ..=1.).contains(&view.y));
+            assert!((1. / 1.5..=1.).contains(&view.scale));
+        }
+    }
+}
+
+#[test]
+fn view_interpolates() {
+    let pan = Pan {
+        start: FULL,
+        end: View {
+            x: 0.,
+            y: 1.,
+            scale: 0.5,
+        },
+    };
+    assert_eq!(pan.view_at(0.), pan.start);
+    assert_eq!(pan.view_at(1.), pan.end);
+    assert_eq!(
+        pan.view_at(0.5),
+        View {
+            x: 0.25,
+            y: 0.75,
+            scale: 0.75,
+        }
+    );
+    assert_eq!(pan.view_at(-1.), pan.start);
+    assert_eq!(pan.view_at(2.), pan.end);
+}
+
+#[test]
+fn fit_preserves_aspect_ratio() {
+    assert_eq!(fit((4000, 3000), (1920, 1080)), (1440, 1080));
+    assert_eq!(fit((3000, 4000), (1080, 1920)), (1080, 1440));
+    assert_eq!(fit((100, 50), (1920, 1080)), (1920, 960));
+}
+
+#[test]
+fn fit_degenerate_sizes() {
+    assert_eq!(fit((10000, 1), (100, 100)), (100, 1));
+    assert_eq!(fit((0, 0), (100, 100)), (1, 1));
+    assert_eq!(fit((100, 100), (0, 0)), (1, 1));
+}
@@ -0,0 +1,116 @@
+//! Tests composing images onto a mat.
+
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use rahmen::Vector;
+use rahmen::config::{Border, Color, Mat};
+use rahmen::mat::Passepartout;
+
+const IMAGE: Rgb<u8> = Rgb([200, 0, 0]);
+const MAT: Rgb<u8> = Rgb([100, 100, 100]);
+const BORDER: Rgb<u8> = Rgb([0, 0, 255]);
+
+fn passepartout(mat_width: u32, border: Option<Border>) -> Passepartout {
+    let mat = Mat {
+        width: Some(mat_width),
+        color: Some(Color(MAT.0)),
+        texture: None,
+    };
+    Passepartout::new(Some(mat), border).unwrap()
+}
+
+fn image(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, IMAGE))
+}
+
+#[test]
+fn image_inside_mat() {
+    let passepartout = passepartout(10, None);
+    assert_eq!(passepartout.inset(), 10);
+    let composed = passepartout
+        .compose((40, 30), Vector::new(5, 0), &image(10, 10))
+        .to_rgb8();
+    assert_eq!(composed.dimensions(), (40, 30));
+    // the anchor is relative to the area inside the mat
+    assert_eq!(*composed.get_pixel(15, 10), IMAGE);
+    assert_eq!(*composed.get_pixel(24, 19), IMAGE);
+    assert_eq!(*composed.get_pixel(14, 10), MAT);
+    assert_eq!(*composed.get_pixel(25, 19), MAT);
+    assert_eq!(*composed.get_pixel(15, 9), MAT);
+    assert_eq!(*composed.get_pixel(0, 0), MAT);
+}
+
+#[test]
+fn border_around_image() {
+    let border = Border {
+        width: Some(2),
+        color: Some(Color(BORDER.0)),
+        shadow: None,
+    };
+    let passepartout = passepartout(3, Some(border));
+    assert_eq!(passepartout.inset(), 5);
+    let composed = passepartout
+        .compose((20, 20), Vector::new(0, 0), &image(10, 10))
+        .to_rgb8();
+    assert_eq!(*composed.get_pixel(5, 5), IMAGE);
+    assert_eq!(*composed.get_pixel(14, 14), IMAGE);
+    for (x, y) in [(3, 3), (4, 5), (15, 14), (16, 16), (10, 4)] {
+        assert_eq!(*composed.get_pixel(x, y), BORDER, "at {}, {}", x, y);
+    }
+    for (x, y) in [(2, 2), (2, 10), (17, 10), (10, 17)] {
+        assert_eq!(*composed.get_pixel(x, y), MAT, "at {}, {}", x, y);
+    }
+}
+
+#[test]
+fn shadow_below_border() {
+    let border = Border {
+        width: Some(1),
+        color: Some(Color(BORDER.0)),
+        shadow: Some(2),
+    };
+    let composed = passepartout(0, Some(border))
+        .compose((20, 20), Vector::new(0, 0), &image(10, 10))
+        .to_rgb8();
+    // the framed image covers 0..12, the shadow is offset to 2..14
+    assert_eq!(*composed.get_pixel(11, 11), BORDER);
+    assert_eq!(*composed.get_pixel(13, 13), Rgb([50, 50, 50]));
+    assert_eq!(*composed.get_pixel(13, 1), MAT);
+    assert_eq!(*composed.get_pixel(14, 14), MAT);
+}
+
+#[test]
+fn background_matches_compose() {
+    let passepartout = passepartout(4, None);
+    let img = image(8, 6);
+    let background = passepartout.background((30, 20), Vector::new(2, 3), img.dimensions());
+    assert_eq!(
+        passepartout.place(background, Vector::new(2, 3), &img),
+        passepartout.compose((30, 20), Vector::new(2, 3), &img)
+    );
+}
+
+#[test]
+fn parses_colors() {
+    assert_eq!(
+        Color::try_from("#f0eDe5".to_string()),
+        Ok(Color([240, 237, 229]))
+    );
+    assert_eq!(
+        Color::try_from("102030".to_string()),
+        Ok(Color([16, 32, 48]))
+    );
+    assert_eq!(String::from(Color([1, 2, 255])), "#0102ff");
+}
+
+#[test]
+fn invalid_colors() {
+    for color in [
+        "", "#", "#fff", "#12345", "#1234567", "#12345g", "#+1+2+3", "#ééé",
+    ] {
+        assert!(
+            Color::try_from(color.to_string()).is_err(),
+            "{:?} should be invalid",
+            color
+        );
+    }
+}
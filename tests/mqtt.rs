@@ -0,0 +1,144 @@
+//! Tests the MQTT integration, against a local mosquitto broker if it is installed.
+#![cfg(feature = "mqtt")]
+
+use std::process::{Child, Command as Process};
+use std::time::{Duration, Instant};
+
+use rahmen::config::Mqtt;
+use rahmen::control::{Command, Reply, Request, Status};
+use rahmen::mqtt::{discovery_messages, parse_command, start};
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS};
+
+fn config(port: u16) -> Mqtt {
+    Mqtt {
+        host: "127.0.0.1".into(),
+        port: Some(port),
+        client_id: Some("living room".into()),
+        username: None,
+        password: None,
+        topic: None,
+        discovery: None,
+        discovery_prefix: None,
+    }
+}
+
+#[test]
+fn parses_commands() {
+    assert_eq!(
+        parse_command("rahmen", "rahmen/next/set", ""),
+        Ok(Command::Next)
+    );
+    assert_eq!(
+        parse_command("rahmen", "rahmen/pause/set", "OFF"),
+        Ok(Command::Resume)
+    );
+    assert_eq!(
+        parse_command("rahmen", "rahmen/power/set", "ON"),
+        Ok(Command::Power { on: true })
+    );
+    assert_eq!(
+        parse_command("rahmen", "rahmen/brightness/set", "50"),
+        Ok(Command::SetBrightness {
+            brightness: Some(0.5)
+        })
+    );
+    assert_eq!(
+        parse_command("rahmen", "rahmen/schedule/set", "PRESS"),
+        Ok(Command::SetBrightness { brightness: None })
+    );
+    assert_eq!(
+        parse_command("rahmen", "rahmen/command", r#"{"command": "status"}"#),
+        Ok(Command::Status)
+    );
+    assert!(parse_command("rahmen", "rahmen/power/set", "maybe").is_err());
+    assert!(parse_command("rahmen", "other/next/set", "").is_err());
+}
+
+#[test]
+fn describes_the_device() {
+    let messages = discovery_messages(&config(1883));
+    let (topic, payload) = messages
+        .iter()
+        .find(|(topic, _)| topic.contains("/light/"))
+        .unwrap();
+    assert_eq!(topic, "homeassistant/light/living_room/screen/config");
+    let payload: serde_json::Value = serde_json::from_str(payload).unwrap();
+    assert_eq!(payload["unique_id"], "living_room_screen");
+    assert_eq!(payload["command_topic"], "rahmen/power/set");
+    assert_eq!(payload["availability_topic"], "rahmen/availability");
+}
+
+/// A mosquitto broker on a local port, stopped when dropped
+struct Broker(Child);
+
+impl Drop for Broker {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn talks_to_a_broker() {
+    let port = 18830 + (std::process::id() % 1000) as u16;
+    let Ok(child) = Process::new("mosquitto")
+        .args(["-p", &port.to_string()])
+        .spawn()
+    else {
+        eprintln!("mosquitto not found, skipping");
+        return;
+    };
+    let _broker = Broker(child);
+    std::thread::sleep(Duration::from_millis(500));
+
+    // a slideshow answering the commands
+    let (sender, requests) = std::sync::mpsc::channel::<Request>();
+    let (commands, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for request in requests {
+            let reply = match &request.command {
+                Command::Status => Reply::status(Status {
+                    path: Some("a.jpg".into()),
+                    index: 0,
+                    caption: Some("Seoul".into()),
+                    remaining: 1.,
+                    paused: false,
+                    powered: true,
+                    brightness: 1.,
+                }),
+                command => {
+                    let _ = commands.send(command.clone());
+                    Reply::ok()
+                }
+            };
+            request.reply(reply);
+        }
+    });
+    start(&config(port), sender).unwrap();
+
+    let (client, mut connection) = Client::new(MqttOptions::new("test", "127.0.0.1", port), 16);
+    client
+        .subscribe("rahmen/caption", QoS::AtLeastOnce)
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let caption = loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match connection.recv_timeout(timeout) {
+            Ok(Ok(Event::Incoming(Packet::Publish(publish)))) => {
+                break String::from_utf8_lossy(&publish.payload).to_string();
+            }
+            Ok(_) => {}
+            Err(err) => panic!("no caption received: {:?}", err),
+        }
+    };
+    assert_eq!(caption, "Seoul");
+
+    client
+        .publish("rahmen/next/set", QoS::AtLeastOnce, false, "")
+        .unwrap();
+    std::thread::spawn(move || for _ in connection.iter() {});
+    assert_eq!(
+        received.recv_timeout(Duration::from_secs(10)).unwrap(),
+        Command::Next
+    );
+}
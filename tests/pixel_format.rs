@@ -0,0 +1,179 @@
+//! Tests packing pixels into framebuffer pixel formats.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use rahmen::config::Rotation;
+use rahmen::display_framebuffer::{Channel, PixelFormat};
+
+const RGB565: PixelFormat = PixelFormat {
+    bytes_per_pixel: 2,
+    red: Channel {
+        offset: 11,
+        length: 5,
+    },
+    green: Channel {
+        offset: 5,
+        length: 6,
+    },
+    blue: Channel {
+        offset: 0,
+        length: 5,
+    },
+};
+
+const BGR888: PixelFormat = PixelFormat {
+    bytes_per_pixel: 3,
+    red: Channel {
+        offset: 16,
+        length: 8,
+    },
+    green: Channel {
+        offset: 8,
+        length: 8,
+    },
+    blue: Channel {
+        offset: 0,
+        length: 8,
+    },
+};
+
+#[test]
+fn packs_rgb565() {
+    assert_eq!(RGB565.encode([255, 255, 255], 0, 0, false), 0xffff);
+    assert_eq!(RGB565.encode([255, 0, 0], 0, 0, false), 0xf800);
+    assert_eq!(RGB565.encode([0, 255, 0], 0, 0, false), 0x07e0);
+    assert_eq!(RGB565.encode([0, 0, 255], 0, 0, false), 0x001f);
+    let mut target = [0u8; 4];
+    RGB565.write(0xf800, &mut target);
+    assert_eq!(target, [0x00, 0xf8, 0, 0]);
+}
+
+#[test]
+fn packs_rgb888() {
+    let value = BGR888.encode([0x12, 0x34, 0x56], 0, 0, true);
+    assert_eq!(value, 0x123456);
+    let mut target = [0u8; 3];
+    BGR888.write(value, &mut target);
+    assert_eq!(target, [0x56, 0x34, 0x12]);
+}
+
+#[test]
+fn dithers_gradients() {
+    // a level between two RGB565 steps is rounded up at some positions only
+    let levels: Vec<u32> = (0..4)
+        .flat_map(|y| (0..4).map(move |x| RGB565.encode([0, 0, 4], x, y, true)))
+        .collect();
+    assert!(levels.contains(&0));
+    assert!(levels.contains(&1));
+    assert!(levels.iter().all(|&level| level <= 1));
+    // full intensity never overflows into the neighboring channel
+    assert_eq!(RGB565.encode([255, 255, 255], 3, 3, true), 0xffff);
+}
+
+#[test]
+fn copies_32_bit_pixels() {
+    let channel = |offset| Channel { offset, length: 8 };
+    let xrgb8888 = PixelFormat {
+        bytes_per_pixel: 4,
+        red: channel(16),
+        green: channel(8),
+        blue: channel(0),
+    };
+    assert_eq!(xrgb8888.byte_order(), Some([2, 1, 0]));
+    let xbgr8888 = PixelFormat {
+        red: channel(0),
+        blue: channel(16),
+        ..xrgb8888
+    };
+    assert_eq!(xbgr8888.byte_order(), Some([0, 1, 2]));
+    // other formats are packed pixel by pixel
+    assert_eq!(RGB565.byte_order(), None);
+    assert_eq!(BGR888.byte_order(), None);
+    let xrgb2101010 = PixelFormat {
+        red: Channel {
+            offset: 20,
+            length: 10,
+        },
+        ..xrgb8888
+    };
+    assert_eq!(xrgb2101010.byte_order(), None);
+    // the last byte is the alpha channel of the buffer
+    let rgbx8888 = PixelFormat {
+        red: channel(24),
+        green: channel(16),
+        blue: channel(8),
+        ..xrgb8888
+    };
+    assert_eq!(rgbx8888.byte_order(), None);
+}
+
+#[test]
+fn copies_damaged_rows() {
+    let channel = |offset| Channel { offset, length: 8 };
+    let xrgb8888 = PixelFormat {
+        bytes_per_pixel: 4,
+        red: channel(16),
+        green: channel(8),
+        blue: channel(0),
+    };
+    let red = RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 255]));
+    let image = xrgb8888.arrange(&DynamicImage::ImageRgba8(red));
+    // rows padded to 20 bytes
+    let mut frame = [0xaa; 40];
+    xrgb8888.copy_damage(
+        &image,
+        &[(1, 1, 2, 1)],
+        Rotation::None,
+        false,
+        &mut frame,
+        20,
+    );
+    assert!(frame[..24].iter().all(|&byte| byte == 0xaa));
+    assert_eq!(frame[24..32], [0, 0, 255, 0, 0, 0, 255, 0]);
+    assert!(frame[32..].iter().all(|&byte| byte == 0xaa));
+}
+
+#[test]
+fn packs_rotated_pixels() {
+    let image = RgbaImage::from_fn(2, 1, |x, _| {
+        if x == 0 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 255, 255])
+        }
+    });
+    assert_eq!(
+        RGB565.arrange(&DynamicImage::ImageRgba8(image.clone())),
+        image
+    );
+    // the 2x1 image becomes a 1x2 frame
+    let mut frame = [0; 4];
+    RGB565.copy_damage(
+        &image,
+        &[(0, 0, 2, 1)],
+        Rotation::Cw90,
+        false,
+        &mut frame,
+        2,
+    );
+    assert_eq!(frame, [0x00, 0xf8, 0x1f, 0x00]);
+}
+
+#[test]
+fn arranges_scanout_pixels() {
+    let format = PixelFormat::XRGB8888;
+    assert_eq!(format.byte_order(), Some([2, 1, 0]));
+    let image = RgbaImage::from_fn(2, 1, |x, _| Rgba([x as u8 + 1, 10, 20, 255]));
+    let arranged = format.arrange(&DynamicImage::ImageRgba8(image));
+    assert_eq!(arranged.as_raw(), &[20, 10, 1, 0, 20, 10, 2, 0]);
+    // rotated by 180 degrees, the pixels swap places
+    let mut frame = [0; 8];
+    format.copy_damage(
+        &arranged,
+        &[(0, 0, 2, 1)],
+        Rotation::Cw180,
+        false,
+        &mut frame,
+        8,
+    );
+    assert_eq!(frame, [20, 10, 2, 0, 20, 10, 1, 0]);
+}
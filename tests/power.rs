@@ -0,0 +1,34 @@
+//! Tests switching display power through a sysfs attribute, using a temporary file in place of
+//! `/sys/class/graphics/fb0/blank`.
+
+mod common;
+
+use common::temp_path;
+use rahmen::power::SysfsPower;
+use std::path::PathBuf;
+
+#[test]
+fn writes_blank_values() {
+    let path = temp_path("power-blank");
+    let power = SysfsPower::new(&path);
+    power.power_off().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "4");
+    power.power_on().unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "0");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn missing_attribute_is_error() {
+    let power = SysfsPower::new(temp_path("power-missing").join("blank"));
+    assert!(power.power_off().is_err());
+}
+
+#[test]
+fn derives_path_from_framebuffer_device() {
+    assert_eq!(
+        SysfsPower::for_framebuffer("/dev/fb1").unwrap().path(),
+        PathBuf::from("/sys/class/graphics/fb1/blank")
+    );
+    assert!(SysfsPower::for_framebuffer("/dev/null").is_none());
+}
@@ -0,0 +1,67 @@
+//! Tests rotating the screen content.
+
+use rahmen::config::Rotation;
+
+const ROTATIONS: [Rotation; 4] = [
+    Rotation::None,
+    Rotation::Cw90,
+    Rotation::Cw180,
+    Rotation::Cw270,
+];
+
+#[test]
+fn degrees_round_trip() {
+    for rotation in ROTATIONS {
+        assert_eq!(Rotation::try_from(u32::from(rotation)), Ok(rotation));
+    }
+    assert_eq!(u32::from(Rotation::Cw270), 270);
+    assert!(Rotation::try_from(45).is_err());
+    assert!(Rotation::try_from(360).is_err());
+}
+
+#[test]
+fn transposed() {
+    assert!(!Rotation::None.is_transposed());
+    assert!(Rotation::Cw90.is_transposed());
+    assert!(!Rotation::Cw180.is_transposed());
+    assert!(Rotation::Cw270.is_transposed());
+}
+
+#[test]
+fn maps_corners() {
+    // a 4x2 image: the top left corner and the top right corner
+    let dimensions = (4, 2);
+    let corners = |rotation: Rotation| {
+        (
+            rotation.map((0, 0), dimensions),
+            rotation.map((3, 0), dimensions),
+        )
+    };
+    assert_eq!(corners(Rotation::None), ((0, 0), (3, 0)));
+    // rotated into a 2x4 image
+    assert_eq!(corners(Rotation::Cw90), ((1, 0), (1, 3)));
+    assert_eq!(corners(Rotation::Cw180), ((3, 1), (0, 1)));
+    assert_eq!(corners(Rotation::Cw270), ((0, 3), (0, 0)));
+}
+
+#[test]
+fn maps_into_rotated_bounds() {
+    let (width, height) = (5, 3);
+    for rotation in ROTATIONS {
+        let (bounds_width, bounds_height) = if rotation.is_transposed() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let mut seen = vec![false; (width * height) as usize];
+        for y in 0..height {
+            for x in 0..width {
+                let (mapped_x, mapped_y) = rotation.map((x, y), (width, height));
+                assert!(mapped_x < bounds_width && mapped_y < bounds_height);
+                let index = (mapped_y * bounds_width + mapped_x) as usize;
+                assert!(!seen[index], "{:?} maps two pixels to one", rotation);
+                seen[index] = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Tests scaling images with the configured filter and sharpening.
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Luma, Rgb, RgbImage};
+use rahmen::config::{Filter, Sharpen};
+use rahmen::dataflow::scale_image;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Scaling {
+    filter: Filter,
+    sharpen: Sharpen,
+}
+
+#[test]
+fn reads_filter_names() {
+    let scaling: Scaling = toml::from_str(
+        r#"
+        filter = "catmullrom"
+        sharpen = { sigma = 0.5, threshold = 2 }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(scaling.filter, Filter::CatmullRom);
+    assert_eq!(
+        scaling.sharpen,
+        Sharpen {
+            sigma: 0.5,
+            threshold: 2
+        }
+    );
+    assert!(toml::from_str::<Scaling>("filter = \"bicubic\"").is_err());
+}
+
+#[test]
+fn maps_to_image_filters() {
+    assert_eq!(FilterType::from(Filter::Nearest), FilterType::Nearest);
+    assert_eq!(FilterType::from(Filter::Triangle), FilterType::Triangle);
+    assert_eq!(FilterType::from(Filter::CatmullRom), FilterType::CatmullRom);
+    assert_eq!(FilterType::from(Filter::Gaussian), FilterType::Gaussian);
+    assert_eq!(FilterType::from(Filter::Lanczos3), FilterType::Lanczos3);
+}
+
+#[test]
+fn fits_into_bounds() {
+    let image = DynamicImage::ImageRgb8(RgbImage::new(400, 300));
+    for filter in [Filter::Nearest, Filter::Triangle, Filter::Lanczos3] {
+        let scaled = scale_image(&image, 200, 200, filter, None);
+        assert_eq!(scaled.dimensions(), (200, 150));
+    }
+}
+
+#[test]
+fn sharpens_edges_only() {
+    // a vertical edge between a dark and a bright half
+    let image = DynamicImage::ImageRgb8(RgbImage::from_fn(40, 20, |x, _| {
+        if x < 20 { Rgb([60; 3]) } else { Rgb([180; 3]) }
+    }));
+    let sharpen = Sharpen {
+        sigma: 1.,
+        threshold: 0,
+    };
+    let plain = scale_image(&image, 20, 10, Filter::Nearest, None).to_luma8();
+    let sharpened = scale_image(&image, 20, 10, Filter::Nearest, Some(sharpen)).to_luma8();
+    // flat areas stay the same, the edge gains contrast
+    assert_eq!(sharpened.get_pixel(2, 5), plain.get_pixel(2, 5));
+    assert_eq!(sharpened.get_pixel(17, 5), plain.get_pixel(17, 5));
+    let Luma([dark]) = *sharpened.get_pixel(9, 5);
+    let Luma([bright]) = *sharpened.get_pixel(10, 5);
+    assert!(dark < 60 && bright > 180, "{} {}", dark, bright);
+}
@@ -0,0 +1,111 @@
+//! Tests the daily dimming and power schedules.
+
+use chrono::NaiveTime;
+use image::{DynamicImage, Rgb, RgbImage};
+use rahmen::config::{Dim, PowerOff, TimeOfDay};
+use rahmen::schedule::{DimSchedule, Dimming, PowerSchedule, in_window};
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn dim(from: NaiveTime, to: NaiveTime, brightness: f32) -> Dim {
+    Dim {
+        from: TimeOfDay(from),
+        to: TimeOfDay(to),
+        brightness: Some(brightness),
+        warm: None,
+        clock_only: None,
+    }
+}
+
+#[test]
+fn window_within_day() {
+    let (from, to) = (time(8, 0), time(17, 30));
+    assert!(!in_window(from, to, time(7, 59)));
+    assert!(in_window(from, to, from));
+    assert!(in_window(from, to, time(12, 0)));
+    assert!(!in_window(from, to, to));
+    assert!(!in_window(from, to, time(23, 0)));
+}
+
+#[test]
+fn window_past_midnight() {
+    let (from, to) = (time(22, 0), time(6, 30));
+    assert!(in_window(from, to, from));
+    assert!(in_window(from, to, time(23, 59)));
+    assert!(in_window(from, to, time(0, 0)));
+    assert!(in_window(from, to, time(6, 29)));
+    assert!(!in_window(from, to, to));
+    assert!(!in_window(from, to, time(12, 0)));
+    assert!(!in_window(from, to, time(21, 59)));
+}
+
+#[test]
+fn empty_window() {
+    assert!(!in_window(time(8, 0), time(8, 0), time(8, 0)));
+    assert!(!in_window(time(8, 0), time(8, 0), time(20, 0)));
+}
+
+#[test]
+fn first_window_wins() {
+    let schedule = DimSchedule::new(vec![
+        dim(time(22, 0), time(6, 0), 0.2),
+        dim(time(20, 0), time(23, 0), 0.5),
+    ]);
+    assert_eq!(schedule.dimming_at(time(12, 0)), None);
+    assert_eq!(schedule.dimming_at(time(21, 0)).unwrap().brightness, 0.5);
+    assert_eq!(schedule.dimming_at(time(22, 30)).unwrap().brightness, 0.2);
+    assert_eq!(schedule.dimming_at(time(1, 0)).unwrap().brightness, 0.2);
+    assert!(!schedule.has_clock_only());
+}
+
+#[test]
+fn power_off_past_midnight() {
+    let schedule = PowerSchedule::new(vec![PowerOff {
+        from: TimeOfDay(time(23, 0)),
+        to: TimeOfDay(time(7, 0)),
+    }]);
+    assert!(schedule.is_off_at(time(3, 0)));
+    assert!(!schedule.is_off_at(time(7, 0)));
+    assert!(!PowerSchedule::default().is_off_at(time(3, 0)));
+}
+
+#[test]
+fn dimming_scales_brightness() {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 2, Rgb([200, 100, 255])));
+    let dimming = Dimming {
+        brightness: 0.5,
+        warm: false,
+        clock_only: false,
+    };
+    let dimmed = dimming.apply(&img).to_rgb8();
+    assert_eq!(dimmed.dimensions(), (2, 2));
+    assert!(dimmed.pixels().all(|pixel| *pixel == Rgb([100, 50, 127])));
+}
+
+#[test]
+fn warm_dimming_reduces_blue() {
+    let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([255, 255, 255])));
+    let dimming = Dimming {
+        brightness: 1.,
+        warm: true,
+        clock_only: false,
+    };
+    assert_eq!(
+        *dimming.apply(&img).to_rgb8().get_pixel(0, 0),
+        Rgb([255, 204, 140])
+    );
+}
+
+#[test]
+fn dim_defaults_and_clamps() {
+    let mut settings = dim(time(0, 0), time(1, 0), 3.);
+    assert_eq!(Dimming::from(&settings).brightness, 1.);
+    settings.brightness = None;
+    settings.clock_only = Some(true);
+    let dimming = Dimming::from(&settings);
+    assert_eq!(dimming.brightness, 0.3);
+    assert!(dimming.clock_only);
+    assert!(DimSchedule::new(vec![settings]).has_clock_only());
+}
@@ -0,0 +1,24 @@
+//! Tests turning signals into commands.
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use rahmen::control::Command;
+use rahmen::signals::forward_signals;
+use signal_hook::consts::{SIGHUP, SIGUSR1, SIGUSR2};
+use signal_hook::low_level::raise;
+
+#[test]
+fn forwards_signals_as_commands() {
+    let (sender, requests) = std::sync::mpsc::channel();
+    forward_signals(sender, || {}).unwrap();
+    for (signal, command) in [
+        (SIGUSR1, Command::Next),
+        (SIGUSR2, Command::Previous),
+        (SIGHUP, Command::ReloadConfig),
+    ] {
+        raise(signal).unwrap();
+        let request = requests.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(request.command, command);
+    }
+}
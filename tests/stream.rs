@@ -0,0 +1,196 @@
+//! Tests serving frames over HTTP on localhost.
+#![cfg(feature = "http")]
+
+mod common;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+use common::temp_path;
+use image::{DynamicImage, Rgba, RgbaImage};
+use rahmen::Vector;
+use rahmen::control::{Command, Reply};
+use rahmen::display::Display;
+use rahmen::display_file::FileDisplay;
+use rahmen::stream::{FrameStream, MAX_CLIENTS, StreamDisplay};
+
+fn connect(stream: &FrameStream, path: &str) -> BufReader<TcpStream> {
+    let mut connection = TcpStream::connect(stream.local_addr().unwrap()).unwrap();
+    write!(
+        connection,
+        "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        path
+    )
+    .unwrap();
+    BufReader::new(connection)
+}
+
+/// Read header lines up to the empty line, returning the content length if given
+fn read_headers(reader: &mut BufReader<TcpStream>) -> (Vec<String>, Option<usize>) {
+    let mut lines = Vec::new();
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end().to_string();
+        if line.is_empty() {
+            return (lines, length);
+        }
+        if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length: ") {
+            length = value.parse().ok();
+        }
+        lines.push(line);
+    }
+}
+
+fn assert_red(jpeg: &[u8]) {
+    let image = image::load_from_memory(jpeg).unwrap().to_rgb8();
+    assert_eq!(image.dimensions(), (16, 8));
+    let pixel = image.get_pixel(4, 4);
+    assert!(
+        pixel[0] > 200 && pixel[1] < 50 && pixel[2] < 50,
+        "{:?}",
+        pixel
+    );
+}
+
+#[test]
+fn serves_snapshots_and_streams() {
+    let mut display = FileDisplay::new(temp_path("stream"), (16, 8), false).unwrap();
+    let mut stream = FrameStream::start("127.0.0.1:0", Some(90.), None).unwrap();
+
+    // there is no snapshot before the first frame
+    let (headers, _) = read_headers(&mut connect(&stream, "/snapshot.jpg"));
+    assert!(headers[0].contains("503"), "{:?}", headers);
+
+    let mut mjpeg = connect(&stream, "/stream.mjpg");
+    let red = RgbaImage::from_pixel(16, 8, Rgba([255, 0, 0, 255]));
+    {
+        let mut display = StreamDisplay::new(&mut display, &mut stream);
+        assert_eq!(display.dimensions(), (16, 8));
+        display
+            .render(0, Vector::new(0, 0), &DynamicImage::ImageRgba8(red))
+            .unwrap();
+        display.update().unwrap();
+    }
+
+    let mut snapshot = connect(&stream, "/snapshot.jpg");
+    let (headers, length) = read_headers(&mut snapshot);
+    assert!(headers[0].contains("200"), "{:?}", headers);
+    let mut jpeg = vec![0; length.unwrap()];
+    snapshot.read_exact(&mut jpeg).unwrap();
+    assert_red(&jpeg);
+
+    let (headers, _) = read_headers(&mut mjpeg);
+    assert!(
+        headers
+            .iter()
+            .any(|h| h.contains("multipart/x-mixed-replace")),
+        "{:?}",
+        headers
+    );
+    let mut boundary = String::new();
+    mjpeg.read_line(&mut boundary).unwrap();
+    assert!(boundary.starts_with("--"));
+    let (_, length) = read_headers(&mut mjpeg);
+    let mut jpeg = vec![0; length.unwrap()];
+    mjpeg.read_exact(&mut jpeg).unwrap();
+    assert_red(&jpeg);
+
+    let (headers, _) = read_headers(&mut connect(&stream, "/missing"));
+    assert!(headers[0].contains("404"), "{:?}", headers);
+}
+
+#[test]
+fn limits_clients() {
+    let mut display = FileDisplay::new(temp_path("stream-clients"), (16, 8), false).unwrap();
+    let mut stream = FrameStream::start("127.0.0.1:0", None, None).unwrap();
+    {
+        let mut display = StreamDisplay::new(&mut display, &mut stream);
+        let red = RgbaImage::from_pixel(16, 8, Rgba([255, 0, 0, 255]));
+        display
+            .render(0, Vector::new(0, 0), &DynamicImage::ImageRgba8(red))
+            .unwrap();
+        display.update().unwrap();
+    }
+    let streams: Vec<_> = (0..MAX_CLIENTS)
+        .map(|_| {
+            let mut mjpeg = connect(&stream, "/stream.mjpg");
+            let (headers, _) = read_headers(&mut mjpeg);
+            assert!(headers[0].contains("200"), "{:?}", headers);
+            mjpeg
+        })
+        .collect();
+    let (headers, _) = read_headers(&mut connect(&stream, "/snapshot.jpg"));
+    assert!(headers[0].contains("503"), "{:?}", headers);
+    drop(streams);
+}
+
+/// Send a request with a JSON body and read the status line and the body of the response
+fn send(stream: &FrameStream, method: &str, path: &str, body: &str) -> (String, String) {
+    send_as(stream, method, path, "application/json", body)
+}
+
+/// Send a request with a body of type `content_type` and return the status line and body of the
+/// response
+fn send_as(
+    stream: &FrameStream,
+    method: &str,
+    path: &str,
+    content_type: &str,
+    body: &str,
+) -> (String, String) {
+    let mut connection = TcpStream::connect(stream.local_addr().unwrap()).unwrap();
+    write!(
+        connection,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        content_type,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut reader = BufReader::new(connection);
+    let (headers, length) = read_headers(&mut reader);
+    let mut body = vec![0; length.unwrap_or(0)];
+    reader.read_exact(&mut body).unwrap();
+    (headers[0].clone(), String::from_utf8(body).unwrap())
+}
+
+#[test]
+fn serves_the_remote_control() {
+    let (sender, requests) = std::sync::mpsc::channel();
+    let stream = FrameStream::start("127.0.0.1:0", None, Some(sender)).unwrap();
+    let (commands, received) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for request in requests {
+            commands.send(request.command.clone()).unwrap();
+            request.reply(Reply::ok());
+        }
+    });
+
+    let (status, body) = send(&stream, "POST", "/api/next", "");
+    assert!(status.contains("200"), "{}", status);
+    assert_eq!(body, r#"{"ok":true}"#);
+    assert_eq!(received.recv().unwrap(), Command::Next);
+
+    send(&stream, "POST", "/api/set_delay", r#"{"seconds": 30}"#);
+    assert_eq!(received.recv().unwrap(), Command::SetDelay { seconds: 30. });
+
+    send(&stream, "GET", "/api/status", "");
+    assert_eq!(received.recv().unwrap(), Command::Status);
+
+    let (status, body) = send(&stream, "POST", "/api/set_delay", r#"{"second": 30}"#);
+    assert!(status.contains("400"), "{}", status);
+    assert!(body.contains("Invalid command"), "{}", body);
+
+    let (status, _) = send(&stream, "POST", "/api/quit", "");
+    assert!(status.contains("404"), "{}", status);
+    let (status, _) = send_as(&stream, "POST", "/api/next", "text/plain", "");
+    assert!(status.contains("415"), "{}", status);
+
+    let (status, body) = send(&stream, "GET", "/remote", "");
+    assert!(status.contains("200"), "{}", status);
+    assert!(body.contains("api/status"));
+}
@@ -0,0 +1,87 @@
+//! Tests for the virtual tags.
+
+use rahmen::config::GpsFormat;
+use rahmen::exiv2::Metadata;
+use rahmen::geocode::Geocoder;
+use rahmen::tags::{format_coordinate, is_virtual, read_virtual};
+
+#[test]
+fn formats_coordinates() {
+    assert_eq!(
+        format_coordinate(-33.448, 'N', 'S', GpsFormat::Dms),
+        "33°26'52.8\"S"
+    );
+    assert_eq!(
+        format_coordinate(8.5417, 'E', 'W', GpsFormat::Dms),
+        "8°32'30.1\"E"
+    );
+    // rounds up to a full minute instead of showing 60 seconds
+    assert_eq!(
+        format_coordinate(47.0 + 59.99 / 3600., 'N', 'S', GpsFormat::Dms),
+        "47°1'0.0\"N"
+    );
+    assert_eq!(
+        format_coordinate(-70.67, 'E', 'W', GpsFormat::Decimal),
+        "-70.67000"
+    );
+}
+
+#[test]
+fn virtual_tags() {
+    assert!(is_virtual("Rahmen.GPS.Position"));
+    assert!(!is_virtual("Rahmen.GPS.Nowhere"));
+    assert!(!is_virtual("Exif.GPSInfo.GPSLatitude"));
+}
+
+fn gps_fixture() -> Metadata {
+    Metadata::new_from_path(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/rahmen-exiv2/testdata/gps.jpg"
+    ))
+    .unwrap()
+}
+
+#[test]
+fn reads_gps_tags() {
+    let metadata = gps_fixture();
+    let read = |key, format| read_virtual(&metadata, key, format, None);
+    assert_eq!(
+        read("Rahmen.GPS.Position", GpsFormat::Dms).as_deref(),
+        Some("33°26'52.8\"S 70°40'12.0\"W")
+    );
+    assert_eq!(
+        read("Rahmen.GPS.Position", GpsFormat::Decimal).as_deref(),
+        Some("-33.44800, -70.67000")
+    );
+    assert_eq!(
+        read("Rahmen.GPS.Altitude", GpsFormat::Dms).as_deref(),
+        Some("570 m")
+    );
+    assert_eq!(
+        read("Rahmen.GPS.Time", GpsFormat::Dms).as_deref(),
+        Some("2026-06-11 14:24:30 UTC")
+    );
+}
+
+#[test]
+fn reads_geo_tags() {
+    let metadata = gps_fixture();
+    let cities = "3871336\tSantiago\tSantiago\t\t-33.45694\t-70.64827\tP\tPPLC\tCL\t\t12\n";
+    let countries = "CL\tCHL\t152\tCI\tChile\n";
+    let geocoder = Geocoder::from_readers(
+        cities.as_bytes(),
+        None::<&[u8]>,
+        Some(countries.as_bytes()),
+        50.,
+    )
+    .unwrap();
+    let read = |key| read_virtual(&metadata, key, GpsFormat::Dms, Some(&geocoder));
+    assert_eq!(read("Rahmen.Geo.City").as_deref(), Some("Santiago"));
+    assert_eq!(read("Rahmen.Geo.Region").as_deref(), Some("12"));
+    assert_eq!(read("Rahmen.Geo.Country").as_deref(), Some("Chile"));
+    assert_eq!(read("Rahmen.Geo.CountryCode").as_deref(), Some("CL"));
+    assert_eq!(
+        read_virtual(&metadata, "Rahmen.Geo.City", GpsFormat::Dms, None),
+        None
+    );
+}
@@ -0,0 +1,23 @@
+//! Tests watching the configuration file.
+
+mod common;
+
+use std::time::Duration;
+
+use common::temp_path;
+use rahmen::control::Command;
+use rahmen::watch::watch_config;
+
+#[test]
+fn reloads_on_changes() {
+    let path = temp_path("watch.toml");
+    std::fs::write(&path, "delay = 10\n").unwrap();
+    let (sender, requests) = std::sync::mpsc::channel();
+    watch_config(path.clone(), Duration::from_millis(10), sender);
+    assert!(requests.recv_timeout(Duration::from_millis(100)).is_err());
+
+    std::fs::write(&path, "delay = 120\n").unwrap();
+    let request = requests.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(request.command, Command::ReloadConfig);
+    std::fs::remove_file(&path).unwrap();
+}